@@ -1,13 +1,28 @@
 use config::File;
 use serde::Deserialize;
 
+use crate::structs::upgrades::Upgrade;
+
 #[derive(Debug, Deserialize)]
 /// Struct that represents the top level config.
 pub struct Config {
-    pub map:      MapConfig,
-    pub players:  PlayerConfig,
-    pub entities: EntityConfig,
-    pub animals:  AnimalConfig,
+    pub map:         MapConfig,
+    pub players:     PlayerConfig,
+    pub entities:    EntityConfig,
+    pub animals:     AnimalConfig,
+    pub items:       ItemConfig,
+    pub progression: ProgressionConfig,
+    pub biomes:      BiomeConfig,
+    pub time:        TimeConfig,
+    pub chat:        ChatConfig,
+    pub admin:       AdminConfig,
+    pub clans:       ClanConfig,
+    pub leaderboard: LeaderboardConfig,
+    pub minimap:     MinimapConfig,
+    pub spawn:       SpawnConfig,
+    pub names:       NameConfig,
+    pub anticheat:   AntiCheatConfig,
+    pub idle:        IdleConfig,
 }
 
 #[derive(Debug, Deserialize)]
@@ -15,6 +30,9 @@ pub struct Config {
 pub struct MapConfig {
     pub size: u16,
 
+    // Random when unset.
+    pub seed: Option<u64>,
+
     pub snow_start: u16,
     pub snow_end:   u16,
 
@@ -43,18 +61,208 @@ pub struct PlayerConfig {
 #[derive(Debug, Deserialize)]
 /// Struct that represents the game object configuration entries.
 pub struct EntityConfig {
+    pub chunk_size:       u16,
+    pub object_spacing:   f32,
     pub trees_per_chunk:  u8,
     pub bushes_per_chunk: u8,
     pub stones_per_chunk: u8,
-    pub gold_per_chunk:   u8,
+    pub gold_in_world:    u8,
 }
 
 #[derive(Debug, Deserialize)]
 /// Struct that represents the animal configuration entries.
 pub struct AnimalConfig {
-    pub max_fish_alive:   u16,
-    pub max_wolf:         u8,
-    pub fish_turn_factor: f32,
+    pub max_fish_alive:     u16,
+    pub max_wolf:           u8,
+    pub fish_turn_factor:   f32,
+    pub wolf_aggro_range:   f32,
+    // Multiplies the aggro range at night.
+    pub wolf_night_aggro:   f32,
+    pub wolf_bite_damage:   f32,
+    // ms
+    pub wolf_bite_cooldown: u32,
+    // Fraction of the max health below which wolves flee.
+    pub wolf_flee_health:   f32,
+    // ms
+    pub fish_respawn_delay: u32,
+    pub wolf_respawn_delay: u32,
+    // Animals never respawn closer than this to a player.
+    pub spawn_safe_range:   f32,
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct that represents the usable item configuration entries.
+pub struct ItemConfig {
+    pub apple_heal:      f32,
+    pub apple_food_cost: u32,
+    // ms
+    pub apple_cooldown:  u32,
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct that represents the age and XP configuration entries.
+pub struct ProgressionConfig {
+    pub max_age:   u8,
+    // XP needed to reach age 2, multiplied by the growth every age.
+    pub first_age: u32,
+    pub xp_growth: f32,
+    // XP per gathered resource.
+    pub gather_xp: u32,
+    pub kill_xp:   u32,
+    // The upgrade tree.
+    pub upgrades:  Vec<Upgrade>,
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct that represents the day and night cycle configuration entries.
+pub struct TimeConfig {
+    // ms
+    pub day_length:           u32,
+    // Fraction of the day that is night.
+    pub night_length:         f32,
+    // ms between two time syncs sent to the clients.
+    pub sync_interval:        u32,
+    // Multiplies the animal respawn delays at night.
+    pub night_respawn_factor: f32,
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct that represents the chat configuration entries.
+pub struct ChatConfig {
+    // chars
+    pub max_length:  u16,
+    // Messages allowed per window.
+    pub rate_limit:  u8,
+    // ms
+    pub rate_window: u32,
+    // Deliver to every player, or only to the ones in range.
+    pub global:      bool,
+    pub range:       f32,
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct that represents the admin configuration entries.
+pub struct AdminConfig {
+    // Tokens accepted by '/auth'.
+    pub tokens: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct that represents the clan configuration entries.
+pub struct ClanConfig {
+    // chars
+    pub max_name_length: u16,
+    // The owner included.
+    pub max_members:     u8,
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct that represents the leaderboard configuration entries.
+pub struct LeaderboardConfig {
+    // Players shown.
+    pub size:     u8,
+    pub score:    LeaderboardScore,
+    // ms between two checks for changes.
+    pub interval: u32,
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct that represents the minimap configuration entries.
+pub struct MinimapConfig {
+    // ms between two minimap updates.
+    pub interval:      u32,
+    // ms a player waits between two map pings.
+    pub ping_cooldown: u32,
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct that represents the player spawn configuration entries.
+pub struct SpawnConfig {
+    // ms during which a spawned player can't be damaged.
+    pub protection:  u32,
+    // No enemy player or wolf is this close to a spawn.
+    pub enemy_range: f32,
+    // Players spawning near their clan land this close to a member.
+    pub clan_range:  f32,
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct that represents the player name configuration entries.
+pub struct NameConfig {
+    // chars
+    pub max_length: u16,
+    // Given to players spawning without a name.
+    pub default:    String,
+    // Names no player can take, matched ignoring case and symbols.
+    pub reserved:   Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct that represents the anti-cheat configuration entries.
+pub struct AntiCheatConfig {
+    // Suspicion at which the action is taken.
+    pub threshold:        f32,
+    // Suspicion forgiven per second.
+    pub decay:            f32,
+    pub action:           AntiCheatAction,
+    // Furthest a player can move in a tick, knockback and collisions included.
+    pub max_step:         f32,
+    // Share of the weapon reload two attacks must at least be apart.
+    pub attack_tolerance: f32,
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct that represents the idle player configuration entries.
+pub struct IdleConfig {
+    // ms without input before the player is warned.
+    pub warning: u32,
+    // ms without input before the player is disconnected.
+    pub timeout: u32,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// What is done to a player whose suspicion reaches the threshold.
+pub enum AntiCheatAction {
+    // Disconnect them.
+    Kick,
+    // Log them and tell the admins.
+    Flag,
+    // Silently keep their attacks from landing.
+    Limit,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// What players are ranked by on the leaderboard.
+pub enum LeaderboardScore {
+    Gold,
+    Kills,
+    Xp,
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct that represents the effects of every biome.
+pub struct BiomeConfig {
+    pub snow:      BiomeEffects,
+    pub grassland: BiomeEffects,
+    pub desert:    BiomeEffects,
+    pub lava:      BiomeEffects,
+    pub ocean:     BiomeEffects,
+}
+
+#[derive(Debug, Deserialize)]
+/// Struct that represents the effects of a biome on the players in it.
+pub struct BiomeEffects {
+    // Multiplies the speed of moving players.
+    pub speed:     f32,
+    // Multiplies the friction slowing down idle players.
+    pub friction:  f32,
+    // Health per second, negative values heal.
+    pub damage:    f32,
+    // Velocity the players drift with.
+    pub current_x: f32,
+    pub current_y: f32,
 }
 
 /// Loads the config file and attempts to parse it.
@@ -62,5 +270,45 @@ pub fn load_config() -> Result<Config, config::ConfigError> {
     let config = config::Config::builder()
         .add_source(File::with_name("src/config/config.toml"))
         .build()?;
-    config.try_deserialize()
+    let parsed: Config = config.try_deserialize()?;
+
+    // The world clock divides by these.
+    for (name, value) in [
+        ("time.day_length", parsed.time.day_length),
+        ("time.sync_interval", parsed.time.sync_interval),
+        ("leaderboard.interval", parsed.leaderboard.interval),
+        ("minimap.interval", parsed.minimap.interval),
+    ] {
+        if value == 0 {
+            return Err(config::ConfigError::Message(format!("{} can't be 0", name)));
+        }
+    }
+    check_upgrades(&parsed.progression).map_err(config::ConfigError::Message)?;
+
+    Ok(parsed)
+}
+
+/// Checks that the upgrade ids are unique, that every upgrade unlocks at an age
+/// players reach, and that the upgrades they require come at an earlier age.
+fn check_upgrades(progression: &ProgressionConfig) -> Result<(), String> {
+    let upgrades = &progression.upgrades;
+
+    for (i, upgrade) in upgrades.iter().enumerate() {
+        if upgrades[..i].iter().any(|u| u.id == upgrade.id) {
+            return Err(format!("upgrade {} is defined twice", upgrade.id));
+        }
+        // Players start at age 1 without an upgrade point.
+        if !(2..=progression.max_age).contains(&upgrade.age) {
+            return Err(format!("upgrade {} unlocks at age {}", upgrade.id, upgrade.age));
+        }
+        if let Some(required) = upgrade.requires {
+            if !upgrades.iter().any(|u| u.id == required && u.age < upgrade.age) {
+                return Err(format!(
+                    "upgrade {} requires {}, which doesn't unlock before it",
+                    upgrade.id, required
+                ));
+            }
+        }
+    }
+    Ok(())
 }
@@ -5,10 +5,24 @@ use thiserror::Error;
 #[derive(Debug)]
 pub enum InternalGameMessages {
     Disconnect,
-    AddPlayer(Player),
+    // the player, whether they spawn near their clan
+    AddPlayer(Player, bool),
     MovePlayer(Move),
     AimPlayer(Aim),
     PlayerHit(HitEvent),
+    UseItem(u8),
+    ChooseUpgrade(u8),
+    StoreBuy(u8, u8),
+    StoreEquip(u8, Option<u8>),
+    Chat(String),
+    ClanCreate(String),
+    ClanJoin(u32),
+    ClanLeave,
+    ClanAccept(u32),
+    ClanKick(u32),
+    MapPing(f32, f32),
+    // a direction that isn't a number
+    InvalidInput,
 }
 
 #[derive(Debug, Error)]
@@ -42,6 +56,54 @@ pub enum ClientProducedError {
 
     #[error("received malformed message")]
     FaultyMessage,
+
+    #[error("invalid upgrade choice: {0}")]
+    InvalidUpgrade(&'static str),
+
+    #[error("invalid store action: {0}")]
+    InvalidStoreAction(&'static str),
+
+    #[error("invalid chat message: {0}")]
+    InvalidChat(&'static str),
+
+    #[error("invalid clan action: {0}")]
+    InvalidClanAction(&'static str),
+
+    #[error("invalid map ping: {0}")]
+    InvalidPing(&'static str),
+}
+
+/// why a spawn request was refused, sent to the client.
+#[derive(Debug, Clone, Copy, Error)]
+#[repr(u8)]
+pub enum SpawnRejection {
+    #[error("name too long")]
+    NameTooLong = 0,
+
+    #[error("reserved name")]
+    ReservedName = 1,
+
+    #[error("already spawned")]
+    AlreadySpawned = 2,
+}
+
+/// errors of chat commands, shown to the player
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error("unknown command, try /help")]
+    Unknown,
+
+    #[error("usage: {0}")]
+    Usage(&'static str),
+
+    #[error("you are not allowed to use this command")]
+    NotAllowed,
+
+    #[error("no player with id {0}")]
+    NoPlayer(u32),
+
+    #[error("invalid admin token")]
+    InvalidToken,
 }
 
 impl From<borsh::io::Error> for ClientProducedError {
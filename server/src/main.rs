@@ -4,8 +4,7 @@ use std::sync::{atomic::AtomicU32, Arc};
 struct CollisionSet;
 
 use bevy_ecs::{prelude::*, schedule::ScheduleBuildSettings};
-use dashmap::DashMap;
-use nanorand::WyRand;
+use dashmap::{DashMap, DashSet};
 use parking_lot::Mutex;
 use tokio::sync::mpsc as god;
 use wtransport::*;
@@ -14,10 +13,19 @@ use crate::{
     config::config::{load_config, Config},
     errors::InternalGameMessages,
     structs::{
-        bevy::{IDToConnection, InputMap, PlayerConnection, PlayerMap, World},
-        components::{HitEvents, PlayerPositions},
+        bevy::{BanList, IDToConnection, InputMap, PlayerConnection, PlayerMap, World},
+        clans::Clans,
+        clock::WorldClock,
+        components::{
+            Deaths, HealthUpdates, HitEvents, PendingSpawns, PlayerPositions, ProgressionUpdates, ResourceUpdates,
+            Violations,
+        },
+        idle::IdleTimers,
+        leaderboard::Leaderboard,
+        rng::{AiRng, LootRng, MapGenRng, PlayerSpawnRng, RngStream, SpawnRng, WorldSeed},
+        spatial_index::SpatialIndex,
     },
-    systems::{init_animals, init_map, GlobalRng},
+    systems::{init_animals, init_map},
 };
 
 mod config;
@@ -53,18 +61,14 @@ async fn main() -> anyhow::Result<()> {
     // Load config.
     let config = load_config().expect("failed to load config");
 
-    // Create a bevy schedule for the systems.
-    let mut schedule = Schedule::default();
-    schedule.set_executor_kind(bevy_ecs::schedule::ExecutorKind::MultiThreaded);
-    schedule.set_build_settings(ScheduleBuildSettings::default());
+    // Addresses banned by the admins, shared with the connection loop.
+    let bans: BanList = Arc::new(DashSet::new());
 
-    // Register the systems.
-    schedule.add_systems((systems::movement_system, systems::animal_ai_system).before(CollisionSet));
-    schedule.add_systems((systems::collision_resolution_system, systems::attack_system).in_set(CollisionSet));
     let world = Arc::new(Mutex::new(World {
         bevy_world: bevy_ecs::world::World::new(),
         config,
-        schedule,
+        schedule: build_schedule(),
+        bans: bans.clone(),
         // schedule: Schedule::default()
         //     .set_executor_kind(bevy_ecs::schedule::ExecutorKind::MultiThreaded)
         //     .set_build_settings(ScheduleBuildSettings::default())
@@ -75,15 +79,15 @@ async fn main() -> anyhow::Result<()> {
     }));
 
     let mut w = world.lock();
-    let mut rng = GlobalRng(WyRand::new());
 
-    init_map(&mut w.bevy_world, &mut rng);
-    init_animals(&mut w.bevy_world, &mut rng);
+    // The seed passed on the command line takes priority over the config one.
+    let seed = seed_arg()
+        .or(w.config.map.seed)
+        .map(WorldSeed)
+        .unwrap_or_else(WorldSeed::random);
+    tracing::info!("world seed: {}", seed.0);
 
-    w.bevy_world.insert_resource(PlayerMap::default());
-    w.bevy_world.insert_resource(rng);
-    w.bevy_world.insert_resource(HitEvents::default());
-    w.bevy_world.insert_resource(PlayerPositions::default());
+    init_world(&mut w.bevy_world, seed);
 
     drop(w);
 
@@ -116,6 +120,7 @@ async fn main() -> anyhow::Result<()> {
         let connections = player_connections.clone();
         let tx = input_tx.clone();
         let input_map = input_map.clone();
+        let bans = bans.clone();
 
         async move {
             loop {
@@ -127,6 +132,13 @@ async fn main() -> anyhow::Result<()> {
                     }
                 };
 
+                // Turn away banned addresses before the session is accepted.
+                if bans.contains(&incoming_session.remote_address().ip()) {
+                    tracing::info!("refused banned address {}", incoming_session.remote_address());
+                    tokio::spawn(incoming_session.forbidden());
+                    continue;
+                }
+
                 let player_id = next_id.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
                 let connection_map = connections.clone();
                 let game_tx = tx.clone();
@@ -175,3 +187,172 @@ async fn main() -> anyhow::Result<()> {
     // Return a pending future to keep the runtime alive.
     Ok(std::future::pending::<()>().await)
 }
+
+/// Returns the seed passed with '--seed <n>', if any.
+fn seed_arg() -> Option<u64> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--seed" {
+            return args.next().and_then(|s| s.parse().ok());
+        }
+    }
+    None
+}
+
+/// Returns the schedule of the systems run every tick.
+fn build_schedule() -> Schedule {
+    let mut schedule = Schedule::default();
+    schedule.set_executor_kind(bevy_ecs::schedule::ExecutorKind::MultiThreaded);
+    schedule.set_build_settings(ScheduleBuildSettings::default());
+
+    // Register the systems.
+    schedule.add_systems(
+        (
+            // The clock advances first, every system sees the time of the tick.
+            systems::clock_system,
+            (
+                systems::movement_system,
+                (systems::item_system, systems::equipment_system, systems::biome_system).chain(),
+            ),
+            // Wolves chase and bite players, after they moved and healed.
+            systems::animal_ai_system,
+            systems::population_system,
+        )
+            .chain()
+            .before(CollisionSet),
+    );
+    // Chained, both touch positions and their order must not depend on the executor.
+    // The spatial index is rebuilt first, once the positions of the tick are known.
+    schedule.add_systems(
+        (
+            systems::spatial_index_system,
+            systems::collision_resolution_system,
+            // Positions are final once the collisions are resolved.
+            systems::anticheat_system,
+            systems::attack_system,
+            systems::animal_death_system,
+            systems::player_death_system,
+            systems::progression_system,
+        )
+            .chain()
+            .in_set(CollisionSet),
+    );
+
+    schedule
+}
+
+/// Generates the world of the seed, and inserts the resources the systems use.
+fn init_world(world: &mut bevy_ecs::world::World, seed: WorldSeed) {
+    let mut map_rng = MapGenRng(seed.stream(RngStream::MapGen));
+
+    init_map(world, &mut map_rng);
+    init_animals(world, &mut map_rng);
+
+    world.insert_resource(PlayerMap::default());
+    world.insert_resource(seed);
+    world.insert_resource(AiRng(seed.stream(RngStream::Ai)));
+    world.insert_resource(LootRng(seed.stream(RngStream::Loot)));
+    world.insert_resource(SpawnRng(seed.stream(RngStream::Spawn)));
+    world.insert_resource(PlayerSpawnRng(seed.stream(RngStream::PlayerSpawn)));
+    world.insert_resource(HitEvents::default());
+    world.insert_resource(HealthUpdates::default());
+    world.insert_resource(ResourceUpdates::default());
+    world.insert_resource(ProgressionUpdates::default());
+    world.insert_resource(PlayerPositions::default());
+    world.insert_resource(PendingSpawns::default());
+    world.insert_resource(Deaths::default());
+    world.insert_resource(Violations::default());
+    world.insert_resource(IdleTimers::default());
+    world.insert_resource(SpatialIndex::default());
+    world.insert_resource(WorldClock::default());
+    world.insert_resource(Clans::default());
+    world.insert_resource(Leaderboard::default());
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::structs::server::{HitEvent, Player};
+
+    use super::*;
+    use crate::structs::components::{Health, Position, Resources};
+
+    const PLAYERS: u32 = 3;
+    const TICKS: u32 = 200;
+
+    /// Returns the messages the players send before the tick, and sets their
+    /// inputs. Players spawn, walk and aim around, attack and eat.
+    fn script(tick: u32, input_map: &InputMap) -> Vec<(u32, InternalGameMessages)> {
+        let mut messages = Vec::new();
+        for id in 0..PLAYERS {
+            if tick == 0 {
+                messages.push((
+                    id,
+                    InternalGameMessages::AddPlayer(Player::new(format!("p{}", id), id), false),
+                ));
+                continue;
+            }
+
+            if let Some(input) = input_map.get(&id) {
+                let moving = !(tick / 20 + id).is_multiple_of(3);
+                input.set_move(moving.then_some(((tick / 10 + id * 7) % 16) as f32 * 0.4));
+                input.set_aim(tick as f32 * 0.1 + id as f32);
+            }
+            if (tick + id).is_multiple_of(5) {
+                messages.push((id, InternalGameMessages::PlayerHit(HitEvent {})));
+            }
+            if (tick + id).is_multiple_of(50) {
+                messages.push((id, InternalGameMessages::UseItem(0)));
+            }
+        }
+        messages
+    }
+
+    /// Returns the position, health and resources of every entity.
+    fn snapshot(world: &mut bevy_ecs::world::World) -> Vec<String> {
+        let mut query = world.query::<(Entity, &Position, Option<&Health>, Option<&Resources>)>();
+        let mut entities: Vec<_> = query.iter(world).collect();
+        entities.sort_by_key(|(entity, ..)| *entity);
+        entities.iter().map(|e| format!("{:?}", e)).collect()
+    }
+
+    /// Plays the script in a new world of the seed, returns the snapshot after
+    /// every tick.
+    fn play(seed: WorldSeed, ticks: u32) -> Vec<Vec<String>> {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let connections: IDToConnection = Arc::new(DashMap::new());
+        let input_map: InputMap = Arc::new(DashMap::new());
+
+        let mut world = World {
+            bevy_world: bevy_ecs::world::World::new(),
+            config:     load_config().unwrap(),
+            schedule:   build_schedule(),
+            bans:       Arc::new(DashSet::new()),
+        };
+        init_world(&mut world.bevy_world, seed);
+
+        let mut snapshots = vec![snapshot(&mut world.bevy_world)];
+        for tick in 0..ticks {
+            let messages = script(tick, &input_map);
+            world.tick(messages.into_iter(), &input_map, &connections, runtime.handle());
+            snapshots.push(snapshot(&mut world.bevy_world));
+        }
+        snapshots
+    }
+
+    #[test]
+    fn same_seed_and_inputs_give_the_same_world() {
+        let first = play(WorldSeed(6767), TICKS);
+        let second = play(WorldSeed(6767), TICKS);
+
+        for (tick, (a, b)) in first.iter().zip(&second).enumerate() {
+            assert!(a == b, "the worlds differ after {} ticks", tick);
+        }
+        // The players spawned and moved.
+        assert_ne!(first[1], first[TICKS as usize]);
+    }
+
+    #[test]
+    fn seeds_give_different_worlds() {
+        assert_ne!(play(WorldSeed(1), 0), play(WorldSeed(2), 0));
+    }
+}
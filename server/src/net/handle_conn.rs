@@ -1,6 +1,7 @@
 use wtransport::VarInt;
 
 use crate::{
+    errors::InternalGameMessages,
     net::{handle_msgs::handle_msg, perform_handshake},
     structs::bevy::{InputMap, PlayerConnection},
     ConnectionMap, GameChannel,
@@ -90,4 +91,8 @@ pub async fn handle_conn(
         handle_msg(opcode, &plaintext[1..], player_id, &game_tx, &input_map).await;
     }
     tracing::info!("player {} disconnected", player_id);
+
+    // Forget the connection, and remove the player from the world.
+    connection_map.remove(&player_id);
+    let _ = game_tx.send((player_id, InternalGameMessages::Disconnect)).await;
 }
@@ -1,6 +1,10 @@
 use shared::{
     structs::server::Player,
-    to_server::{AimMessage, HitMessage, MoveMessage, SpawnMessage},
+    to_server::{
+        AimMessage, ChatMessage, ChooseUpgradeMessage, ClanAcceptMessage, ClanCreateMessage, ClanJoinMessage,
+        ClanKickMessage, HitMessage, MapPingMessage, MoveMessage, SpawnMessage, StoreBuyMessage, StoreEquipMessage,
+        UseItemMessage,
+    },
     PacketType,
 };
 
@@ -12,29 +16,30 @@ pub async fn handle_msg(opcode: u8, data: &[u8], player_id: u32, game_tx: &GameC
     match PacketType::from_u8(opcode) {
         Some(PacketType::Spawn) => {
             if let Ok(data) = decode::<SpawnMessage>(data) {
-                // censor their name.
-                let name = rustrict::Censor::from_str(&data.name)
-                    .with_censor_first_character_threshold(rustrict::Type::OFFENSIVE & rustrict::Type::SEXUAL)
-                    .with_ignore_false_positives(false)
-                    .with_censor_replacement('*')
-                    .censor();
-                
+                // Their name is validated by the game loop.
                 let _ = game_tx
-                    .send((player_id, InternalGameMessages::AddPlayer(Player::new(name, player_id))))
+                    .send((
+                        player_id,
+                        InternalGameMessages::AddPlayer(Player::new(data.name, player_id), data.near_clan),
+                    ))
                     .await;
             }
         }
         Some(PacketType::Move) => {
             if let Ok(data) = decode::<MoveMessage>(data) {
                 if let Some(input) = input_map.get(&player_id) {
-                    input.set_move(data.dir);
+                    if !input.set_move(data.dir) {
+                        let _ = game_tx.try_send((player_id, InternalGameMessages::InvalidInput));
+                    }
                 }
             }
         }
         Some(PacketType::Aim) => {
             if let Ok(data) = decode::<AimMessage>(data) {
                 if let Some(input) = input_map.get(&player_id) {
-                    input.set_aim(data.dir.unwrap_or(0.0));
+                    if !input.set_aim(data.dir.unwrap_or(0.0)) {
+                        let _ = game_tx.try_send((player_id, InternalGameMessages::InvalidInput));
+                    }
                 }
             }
         }
@@ -46,6 +51,60 @@ pub async fn handle_msg(opcode: u8, data: &[u8], player_id: u32, game_tx: &GameC
                 ));
             }
         }
+        Some(PacketType::UseItem) => {
+            if let Ok(data) = decode::<UseItemMessage>(data) {
+                let _ = game_tx.try_send((player_id, InternalGameMessages::UseItem(data.item)));
+            }
+        }
+        Some(PacketType::ChooseUpgrade) => {
+            if let Ok(data) = decode::<ChooseUpgradeMessage>(data) {
+                let _ = game_tx.try_send((player_id, InternalGameMessages::ChooseUpgrade(data.upgrade)));
+            }
+        }
+        Some(PacketType::StoreBuy) => {
+            if let Ok(data) = decode::<StoreBuyMessage>(data) {
+                let _ = game_tx.try_send((player_id, InternalGameMessages::StoreBuy(data.kind, data.id)));
+            }
+        }
+        Some(PacketType::StoreEquip) => {
+            if let Ok(data) = decode::<StoreEquipMessage>(data) {
+                let _ = game_tx.try_send((player_id, InternalGameMessages::StoreEquip(data.kind, data.id)));
+            }
+        }
+        Some(PacketType::Chat) => {
+            if let Ok(data) = decode::<ChatMessage>(data) {
+                let _ = game_tx.try_send((player_id, InternalGameMessages::Chat(data.message)));
+            }
+        }
+        Some(PacketType::ClanCreate) => {
+            if let Ok(data) = decode::<ClanCreateMessage>(data) {
+                let _ = game_tx.try_send((player_id, InternalGameMessages::ClanCreate(data.name)));
+            }
+        }
+        Some(PacketType::ClanJoin) => {
+            if let Ok(data) = decode::<ClanJoinMessage>(data) {
+                let _ = game_tx.try_send((player_id, InternalGameMessages::ClanJoin(data.clan)));
+            }
+        }
+        Some(PacketType::ClanLeave) => {
+            // The message has no data.
+            let _ = game_tx.try_send((player_id, InternalGameMessages::ClanLeave));
+        }
+        Some(PacketType::ClanAccept) => {
+            if let Ok(data) = decode::<ClanAcceptMessage>(data) {
+                let _ = game_tx.try_send((player_id, InternalGameMessages::ClanAccept(data.player)));
+            }
+        }
+        Some(PacketType::ClanKick) => {
+            if let Ok(data) = decode::<ClanKickMessage>(data) {
+                let _ = game_tx.try_send((player_id, InternalGameMessages::ClanKick(data.player)));
+            }
+        }
+        Some(PacketType::MapPing) => {
+            if let Ok(data) = decode::<MapPingMessage>(data) {
+                let _ = game_tx.try_send((player_id, InternalGameMessages::MapPing(data.x, data.y)));
+            }
+        }
         _ => {}
     }
 }
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use bevy_ecs::{entity::Entity, query::With};
+use shared::to_client::SystemMessageData;
+use wtransport::VarInt;
+
+use crate::{
+    broadcast,
+    config::config::AntiCheatAction,
+    structs::{
+        bevy::{IDToConnection, PlayerMap, World},
+        clock::WorldClock,
+        components::{Admin, Flagged, ShadowLimited, Suspicion, Violations},
+        weapons::{get_weapon_attack_speed, Weapon},
+    },
+    CONFIG,
+};
+
+/// Cheats the server catches.
+#[derive(Clone, Copy, Debug)]
+pub enum Violation {
+    // A direction that isn't a number.
+    InvalidInput,
+    // A position outside of the map, or that isn't a number.
+    ImpossiblePosition,
+    // Moved further in a tick than the game allows.
+    Teleport,
+    // Attacks started faster than the weapon swings.
+    AttackRate,
+}
+
+impl Violation {
+    /// Returns the suspicion the violation adds.
+    pub fn weight(&self) -> f32 {
+        match self {
+            Violation::InvalidInput => 20.,
+            Violation::ImpossiblePosition => 50.,
+            Violation::Teleport => 25.,
+            Violation::AttackRate => 5.,
+        }
+    }
+}
+
+/// Records an attack started by the player, it is a violation when it comes
+/// sooner after the last one than their weapon allows.
+pub fn check_attack_rate(world: &mut bevy_ecs::world::World, entity: Entity) {
+    let now = world.resource::<WorldClock>().elapsed_ms();
+    let Some(&weapon) = world.get::<Weapon>(entity) else {
+        return;
+    };
+    let Some(mut suspicion) = world.get_mut::<Suspicion>(entity) else {
+        return;
+    };
+
+    let min_gap = (get_weapon_attack_speed(&weapon) * CONFIG.anticheat.attack_tolerance) as u64;
+    let too_fast = suspicion.1.is_some_and(|last| now < last + min_gap);
+    suspicion.1 = Some(now);
+
+    if too_fast {
+        world
+            .resource_mut::<Violations>()
+            .0
+            .push((entity, Violation::AttackRate));
+    }
+}
+
+/// Adds the violations of the tick to the suspicion of the players, and takes
+/// the configured action against the ones reaching the threshold.
+pub fn handle_violations(
+    world: &mut bevy_ecs::world::World,
+    rt_handle: &tokio::runtime::Handle,
+    connections: &IDToConnection,
+) {
+    let violations = std::mem::take(&mut world.resource_mut::<Violations>().0);
+    if violations.is_empty() {
+        return;
+    }
+
+    let entity_to_id: HashMap<Entity, u32> = world.resource::<PlayerMap>().map.iter().map(|e| (*e.1, *e.0)).collect();
+
+    for (entity, violation) in violations {
+        // Players may have left since.
+        let Some(&id) = entity_to_id.get(&entity) else {
+            continue;
+        };
+        let Some(mut suspicion) = world.get_mut::<Suspicion>(entity) else {
+            continue;
+        };
+
+        suspicion.0 += violation.weight();
+        let score = suspicion.0;
+        tracing::warn!("player {} violation {:?}, suspicion {:.0}", id, violation, score);
+
+        if score < CONFIG.anticheat.threshold {
+            continue;
+        }
+
+        match CONFIG.anticheat.action {
+            AntiCheatAction::Kick => {
+                if let Some(conn) = connections.get(&id) {
+                    tracing::warn!("player {} kicked by the anti-cheat", id);
+                    conn.connection.close(VarInt::from_u32(2), b"kicked");
+                }
+            }
+            AntiCheatAction::Flag => {
+                if world.get::<Flagged>(entity).is_some() {
+                    continue;
+                }
+                world.entity_mut(entity).insert(Flagged);
+                tracing::warn!("player {} flagged by the anti-cheat", id);
+
+                let msg = crate::net::serialization::encode(
+                    23,
+                    SystemMessageData {
+                        message: format!("player {} flagged by the anti-cheat ({:?})", id, violation),
+                    },
+                )
+                .unwrap();
+                let mut admins = world.query_filtered::<Entity, With<Admin>>();
+                for admin in admins.iter(world) {
+                    if let Some(&admin_id) = entity_to_id.get(&admin) {
+                        broadcast!(reliable_to, rt_handle, connections, admin_id, msg.clone());
+                    }
+                }
+            }
+            AntiCheatAction::Limit => {
+                if world.get::<ShadowLimited>(entity).is_some() {
+                    continue;
+                }
+                // They aren't told.
+                world.entity_mut(entity).insert(ShadowLimited);
+                tracing::warn!("player {} limited by the anti-cheat", id);
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
     collections::HashMap,
+    net::IpAddr,
     sync::{
         atomic::{AtomicBool, AtomicU32, AtomicU64},
         Arc,
@@ -10,12 +11,14 @@ use std::{
 use bevy_ecs::prelude::*;
 use bytes::Bytes;
 use chacha20poly1305::aead::Aead;
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
 use parking_lot::Mutex;
 use shared::{
     objects::GameObjects, to_client::{
-        AddAnimalData, AnimalTO, HitEventTO, ObjectHitAnimData, ObjectTO, PlayerTO, SetResourceData, SetWeaponsData,
-        UpdatePlayerData,
+        AddAnimalData, AllyPingData, AnimalTO, BiomeData, BiomeTO, ChatBubbleData, ClanRequestData, DeathData,
+        HitEventTO, KillFeedData, ObjectHitAnimData, ObjectTO, PlayerTO, SetResourceData, SetWeaponsData,
+        SpawnRejectedData, StoreUpdateData, SystemMessageData, UpdateAgeData, UpdateHealthData, UpdatePlayerData,
+        UpgradeOptionsData,
     }, to_server::ClientMessages
 };
 use wtransport::Connection;
@@ -24,13 +27,36 @@ use crate::{
     broadcast,
     errors::InternalGameMessages,
     net::SessionCrypto,
-    structs::components::{
-        AimDir, AnimalType, AttackState, Health, HitEvents, MoveDir, Name, ObjectEntity, PlayerBundle, PlayerEntity,
-        PlayerPositions, Position, ReloadState, Resources, Velocity,
+    structs::{
+        anticheat::{check_attack_rate, handle_violations, Violation},
+        chat::{accept_chat, check_chat_rate},
+        clans::{accept_member, create_clan, kick_member, leave_clan, request_join, Clans},
+        commands::{run_command, CommandContext},
+        clock::{WorldClock, TICK_MS},
+        components::{
+            Age, AimDir, AttackState, ChatLimit, ClanId, Deaths, Equipment, Health, HealthUpdates, HitEvents,
+            ItemCooldowns, LastDamage, LastPing, LastPosition, MoveDir, Name, ObjectEntity, OwnedItems, PlayerBundle,
+            PlayerEntity, PlayerPositions, Position, ProgressionUpdates, ReloadState, ResourceUpdates, Resources,
+            SpawnPoint, SpawnProtection, Suspicion, Upgrades, UseItemState, Velocity, Violations, Xp,
+        },
+        idle::{check_idle, touch},
+        leaderboard::{total_xp, update_leaderboard, Leaderboard},
+        minimap::{accept_ping, minimap_data, minimap_points},
+        spatial_index::{EntryKind, SpatialIndex},
+        spawns::{accept_spawn, find_player_spawn},
+        store::StoreKind,
+        upgrades::available_upgrades,
+        weapons::Weapon,
     },
-    systems::NonReactiveCollider,
+    systems::{buy_store_item, choose_upgrade, equip_store_item, Biome, NonReactiveCollider},
+    CONFIG,
 };
 
+// Half extents of the area around a player that is replicated to them.
+// TODO: move to config file.
+const VIEW_HALF_WIDTH: f32 = 2048.;
+const VIEW_HALF_HEIGHT: f32 = 1152.;
+
 #[derive(Clone)]
 /// Struct that represents a player connection.
 pub struct PlayerConnection {
@@ -140,9 +166,12 @@ pub struct World {
     pub bevy_world: bevy_ecs::world::World,
     pub config:     crate::config::config::Config,
     pub schedule:   bevy_ecs::schedule::Schedule,
+    pub bans:       BanList,
 }
 
 pub type IDToConnection = Arc<DashMap<u32, PlayerConnection>>;
+/// Banned addresses, checked when a connection is accepted.
+pub type BanList = Arc<DashSet<IpAddr>>;
 
 impl World {
     /// Broadcasts the data to all the active connections.
@@ -253,9 +282,29 @@ impl World {
         // Store a mutable reference to the bevy world.
         let bevy = &mut self.bevy_world;
 
+        // Anything but leaving, or sending garbage, is the player doing something.
+        if !matches!(msg, InternalGameMessages::Disconnect | InternalGameMessages::InvalidInput) {
+            touch(bevy, id);
+        }
+
         // Process the message.
         match msg {
-            InternalGameMessages::AddPlayer(p) => {
+            InternalGameMessages::AddPlayer(mut p, near_clan) => {
+                // Check their name, and that they aren't alive already.
+                match accept_spawn(bevy, id, &p.name) {
+                    Ok(name) => p.name = name,
+                    Err(err) => {
+                        tracing::warn!("player {} spawn rejected: {}", id, err);
+                        let msg =
+                            crate::net::serialization::encode(37, SpawnRejectedData { reason: err as u8 }).unwrap();
+                        broadcast!(reliable_to, rt_handle, player_connections, id, msg);
+                        return;
+                    }
+                }
+
+                // Pick where they spawn.
+                (p.x, p.y) = find_player_spawn(bevy, id, near_clan);
+
                 // Insert a new "PlayerInput".
                 input_map.insert(id, PlayerInput::new());
 
@@ -282,7 +331,9 @@ impl World {
                                         x:            pos.0,
                                         y:            pos.1,
                                         aim:          aim.0,
-                                        weapon_index: Some(0),
+                                        weapon_index: bevy.get::<Weapon>(entity).map(|w| *w as u8),
+                                        hat:          bevy.get::<Equipment>(entity).and_then(|e| e.0),
+                                        accessory:    bevy.get::<Equipment>(entity).and_then(|e| e.1),
                                     },
                                 }),
                             )
@@ -314,6 +365,9 @@ impl World {
                             shared::objects::GameObjects::StaticGameObjects(shared::objects::StaticGameObjects::Tree) => 0,
                             shared::objects::GameObjects::StaticGameObjects(shared::objects::StaticGameObjects::Stone) => 1,
                             shared::objects::GameObjects::StaticGameObjects(shared::objects::StaticGameObjects::Bush) => 2,
+                            shared::objects::GameObjects::StaticGameObjects(shared::objects::StaticGameObjects::Gold) => 3,
+                            shared::objects::GameObjects::StaticGameObjects(shared::objects::StaticGameObjects::Pine) => 4,
+                            shared::objects::GameObjects::StaticGameObjects(shared::objects::StaticGameObjects::Cactus) => 5,
 
                             _ => 67,
                         };
@@ -347,19 +401,41 @@ impl World {
                         Velocity(p.vx, p.vy),
                         MoveDir(None),
                         AimDir(0.0),
-                        crate::structs::weapons::Weapon::Fists,
+                        Weapon::Fists,
                         ReloadState(0, 300),
                         Health(100., 100.),
                         AttackState(false),
                         Resources(0, 0, 0, 0, 0),
                         Collider::circle(35.),
                         ReactiveCollider,
+                        UseItemState::default(),
+                        ItemCooldowns::default(),
+                        Xp(0, CONFIG.progression.first_age),
+                        Age(1),
+                        Upgrades::default(),
+                        Equipment::default(),
+                        OwnedItems::default(),
+                        ChatLimit::default(),
+                        LastDamage::default(),
+                        LastPing::default(),
+                        SpawnProtection(bevy.resource::<WorldClock>().elapsed_ms() + CONFIG.spawn.protection as u64),
+                        LastPosition(Position(p.x, p.y)),
+                        SpawnPoint(Position(p.x, p.y)),
+                        Suspicion::default(),
                     ))
                     .id();
 
                 // Insert it into our player map.
                 bevy.resource_mut::<PlayerMap>().map.insert(id, entity);
 
+                // Players keep their clan when they die and spawn again.
+                if let Some(clan) = bevy.resource::<Clans>().clan_of(id) {
+                    bevy.entity_mut(entity).insert(ClanId(clan));
+                }
+
+                // Tell the player about their age.
+                bevy.resource_mut::<ProgressionUpdates>().0.push(entity);
+
                 // Tell the player about themself.
                 let spawn_self = crate::net::serialization::encode(
                     1,
@@ -372,6 +448,8 @@ impl World {
                             y: p.y,
                             aim: 0.0,
                             weapon_index: Some(0),
+                            hat: None,
+                            accessory: None,
                         },
                     }),
                 )
@@ -380,8 +458,51 @@ impl World {
                 // Tell the player about their weapons.
                 let set_weapons = crate::net::serialization::encode(9, SetWeaponsData { weapons: vec![0] }).unwrap();
 
+                // Tell the player about the biomes and their effects.
+                let map = &CONFIG.map;
+                let biomes = crate::net::serialization::encode(
+                    19,
+                    BiomeData {
+                        snow_end:      map.snow_end,
+                        grassland_end: map.grassland_end,
+                        desert_end:    map.desert_end,
+                        ocean_start_x: map.ocean_start_x,
+                        ocean_end_x:   map.ocean_end_x,
+                        biomes:        Biome::ALL
+                            .iter()
+                            .map(|&biome| {
+                                let effects = biome.effects();
+                                BiomeTO {
+                                    biome:     biome as u8,
+                                    speed:     effects.speed,
+                                    friction:  effects.friction,
+                                    damage:    effects.damage,
+                                    current_x: effects.current_x,
+                                    current_y: effects.current_y,
+                                }
+                            })
+                            .collect(),
+                    },
+                )
+                .unwrap();
+
                 broadcast!(reliable_to, rt_handle, player_connections, id, spawn_self);
                 broadcast!(reliable_to, rt_handle, player_connections, id, set_weapons);
+                // Tell the player about the time.
+                let time_sync =
+                    crate::net::serialization::encode(20, bevy.resource::<WorldClock>().sync_data()).unwrap();
+
+                broadcast!(reliable_to, rt_handle, player_connections, id, biomes);
+                broadcast!(reliable_to, rt_handle, player_connections, id, time_sync);
+
+                // Tell the player about the clans.
+                let clans = crate::net::serialization::encode(29, bevy.resource::<Clans>().to_data()).unwrap();
+                broadcast!(reliable_to, rt_handle, player_connections, id, clans);
+
+                // Tell the player about the leaderboard, it is refreshed on the next update.
+                let leaderboard =
+                    crate::net::serialization::encode(31, bevy.resource::<Leaderboard>().to_data()).unwrap();
+                broadcast!(reliable_to, rt_handle, player_connections, id, leaderboard);
 
                 // Tell all existing players about the new player.
                 let spawn_other = crate::net::serialization::encode(
@@ -395,6 +516,8 @@ impl World {
                             y: p.y,
                             aim: 0.0,
                             weapon_index: Some(0),
+                            hat: None,
+                            accessory: None,
                         },
                     }),
                 )
@@ -407,6 +530,12 @@ impl World {
                 // Remove their entry.
                 input_map.remove(&id);
 
+                // Their clan is disbanded if they owned it.
+                if leave_clan(bevy, id) {
+                    let msg = crate::net::serialization::encode(29, bevy.resource::<Clans>().to_data()).unwrap();
+                    broadcast!(reliable, rt_handle, player_connections, msg);
+                }
+
                 // Despawn their bevy linked entity.
                 // TODO: Send a despawn packet to all players, telling them to remove the
                 // player.
@@ -418,10 +547,197 @@ impl World {
                 }
             }
 
+            InternalGameMessages::Chat(text) => {
+                let Some(&e) = bevy.resource::<PlayerMap>().map.get(&id) else {
+                    return;
+                };
+
+                // Messages starting with '/' are commands, the reply is only shown to the
+                // player.
+                if let Some(command) = text.trim().strip_prefix('/') {
+                    if let Err(err) = check_chat_rate(bevy, e) {
+                        tracing::warn!("player {} command rejected: {}", id, err);
+                        return;
+                    }
+
+                    let mut ctx = CommandContext {
+                        world: bevy,
+                        bans: &self.bans,
+                        connections: player_connections,
+                        rt_handle,
+                        id,
+                        entity: e,
+                    };
+                    let message = run_command(&mut ctx, command).unwrap_or_else(|err| err.to_string());
+
+                    let msg = crate::net::serialization::encode(23, SystemMessageData { message }).unwrap();
+                    broadcast!(reliable_to, rt_handle, player_connections, id, msg);
+                    return;
+                }
+
+                match accept_chat(bevy, e, &text) {
+                    Ok(message) => {
+                        let msg = crate::net::serialization::encode(22, ChatBubbleData { id, message }).unwrap();
+
+                        if CONFIG.chat.global {
+                            broadcast!(reliable, rt_handle, player_connections, msg);
+                        } else if let Some(pos) = bevy.get::<Position>(e) {
+                            // Everyone in range hears it, the sender included.
+                            let range_sq = CONFIG.chat.range * CONFIG.chat.range;
+                            for (&other, &(x, y)) in bevy.resource::<PlayerPositions>().0.iter() {
+                                if (x - pos.0).powi(2) + (y - pos.1).powi(2) <= range_sq {
+                                    broadcast!(reliable_to, rt_handle, player_connections, other, msg.clone());
+                                }
+                            }
+                        }
+                    }
+                    Err(err) => tracing::warn!("player {} chat rejected: {}", id, err),
+                }
+            }
+
+            InternalGameMessages::ClanCreate(name) => match create_clan(bevy, id, &name) {
+                Ok(clan) => {
+                    tracing::info!("player {} created clan {}", id, clan);
+
+                    let msg = crate::net::serialization::encode(29, bevy.resource::<Clans>().to_data()).unwrap();
+                    broadcast!(reliable, rt_handle, player_connections, msg);
+                }
+                Err(err) => tracing::warn!("player {} failed to create a clan: {}", id, err),
+            },
+
+            InternalGameMessages::ClanJoin(clan) => match request_join(bevy, id, clan) {
+                Ok(owner) => {
+                    // Only the owner hears about the request, membership changes once they
+                    // accept.
+                    let Some(name) = bevy
+                        .resource::<PlayerMap>()
+                        .map
+                        .get(&id)
+                        .and_then(|&e| bevy.get::<Name>(e))
+                        .map(|n| n.0.clone())
+                    else {
+                        return;
+                    };
+
+                    let msg = crate::net::serialization::encode(30, ClanRequestData { id, name }).unwrap();
+                    broadcast!(reliable_to, rt_handle, player_connections, owner, msg);
+                }
+                Err(err) => tracing::warn!("player {} failed to join clan {}: {}", id, clan, err),
+            },
+
+            InternalGameMessages::ClanLeave => {
+                // Nothing changes for players outside of clans.
+                if !leave_clan(bevy, id) {
+                    return;
+                }
+
+                let msg = crate::net::serialization::encode(29, bevy.resource::<Clans>().to_data()).unwrap();
+                broadcast!(reliable, rt_handle, player_connections, msg);
+            }
+
+            InternalGameMessages::ClanAccept(player) => match accept_member(bevy, id, player) {
+                Ok(()) => {
+                    let msg = crate::net::serialization::encode(29, bevy.resource::<Clans>().to_data()).unwrap();
+                    broadcast!(reliable, rt_handle, player_connections, msg);
+                }
+                Err(err) => tracing::warn!("player {} failed to accept {}: {}", id, player, err),
+            },
+
+            InternalGameMessages::ClanKick(player) => match kick_member(bevy, id, player) {
+                Ok(()) => {
+                    let msg = crate::net::serialization::encode(29, bevy.resource::<Clans>().to_data()).unwrap();
+                    broadcast!(reliable, rt_handle, player_connections, msg);
+                }
+                Err(err) => tracing::warn!("player {} failed to kick {}: {}", id, player, err),
+            },
+
+            InternalGameMessages::MapPing(x, y) => match accept_ping(bevy, id, x, y) {
+                Ok(mates) => {
+                    let msg = crate::net::serialization::encode(36, AllyPingData { id, x, y }).unwrap();
+                    for mate in mates {
+                        broadcast!(reliable_to, rt_handle, player_connections, mate, msg.clone());
+                    }
+                }
+                Err(err) => tracing::warn!("player {} sent an invalid map ping: {}", id, err),
+            },
+
+            InternalGameMessages::InvalidInput => {
+                if let Some(&e) = bevy.resource::<PlayerMap>().map.get(&id) {
+                    bevy.resource_mut::<Violations>().0.push((e, Violation::InvalidInput));
+                }
+            }
+
             InternalGameMessages::PlayerHit(_) => {
                 if let Some(&e) = bevy.resource::<PlayerMap>().map.get(&id) {
                     if let Some(mut hit) = bevy.get_mut::<AttackState>(e) {
                         hit.0 = !hit.0;
+                        if hit.0 {
+                            check_attack_rate(bevy, e);
+                        }
+                    }
+                }
+            }
+
+            InternalGameMessages::UseItem(item) => {
+                if let Some(&e) = bevy.resource::<PlayerMap>().map.get(&id) {
+                    if let Some(mut use_item) = bevy.get_mut::<UseItemState>(e) {
+                        use_item.0 = Some(item);
+                    }
+                }
+            }
+
+            InternalGameMessages::ChooseUpgrade(upgrade) => {
+                if let Some(&e) = bevy.resource::<PlayerMap>().map.get(&id) {
+                    match choose_upgrade(bevy, e, upgrade) {
+                        Ok(weapon) => {
+                            // Tell the player about their new weapon.
+                            if let Some(weapon) = weapon {
+                                let set_weapons = crate::net::serialization::encode(
+                                    9,
+                                    SetWeaponsData {
+                                        weapons: vec![weapon as u8],
+                                    },
+                                )
+                                .unwrap();
+
+                                broadcast!(reliable_to, rt_handle, player_connections, id, set_weapons);
+                            }
+
+                            // Health changes are replicated, upgrade options are refreshed.
+                            if let Some(health) = bevy.get::<Health>(e).map(|h| h.0) {
+                                bevy.resource_mut::<HealthUpdates>().0.push((e, health));
+                            }
+                            bevy.resource_mut::<ProgressionUpdates>().0.push(e);
+                        }
+                        Err(err) => tracing::warn!("player {} sent an invalid upgrade {}: {}", id, upgrade, err),
+                    }
+                }
+            }
+
+            InternalGameMessages::StoreBuy(kind, item) => {
+                if let Some(&e) = bevy.resource::<PlayerMap>().map.get(&id) {
+                    match buy_store_item(bevy, e, kind, item) {
+                        Ok(()) => {
+                            // Tell the player about the items they own.
+                            if let (Some(owned), Some(store_kind)) =
+                                (bevy.get::<OwnedItems>(e), StoreKind::from_u8(kind))
+                            {
+                                let owned = owned.get(store_kind).clone();
+                                let msg =
+                                    crate::net::serialization::encode(18, StoreUpdateData { kind, owned }).unwrap();
+
+                                broadcast!(reliable_to, rt_handle, player_connections, id, msg);
+                            }
+                        }
+                        Err(err) => tracing::warn!("player {} failed to buy {}/{}: {}", id, kind, item, err),
+                    }
+                }
+            }
+
+            InternalGameMessages::StoreEquip(kind, item) => {
+                if let Some(&e) = bevy.resource::<PlayerMap>().map.get(&id) {
+                    if let Err(err) = equip_store_item(bevy, e, kind, item) {
+                        tracing::warn!("player {} failed to equip {}/{:?}: {}", id, kind, item, err);
                     }
                 }
             }
@@ -455,6 +771,28 @@ impl World {
             pos_cache.0.insert(id, pos);
         }
 
+        // Keep the clients' clocks in sync.
+        let clock = bevy.resource::<WorldClock>();
+        if clock.sync_due() {
+            let msg = crate::net::serialization::encode(20, clock.sync_data()).unwrap();
+            broadcast!(rt_handle, player_connections, msg);
+        }
+
+        // Keep the leaderboard up to date, it is only sent when it changes.
+        if clock.every(CONFIG.leaderboard.interval) && update_leaderboard(bevy) {
+            let msg = crate::net::serialization::encode(31, bevy.resource::<Leaderboard>().to_data()).unwrap();
+            broadcast!(reliable, rt_handle, player_connections, msg);
+        }
+
+        // The minimap is only refreshed now and then, losing one is fine.
+        if bevy.resource::<WorldClock>().every(CONFIG.minimap.interval) {
+            let points = minimap_points(bevy);
+            for (&id, &entity) in bevy.resource::<PlayerMap>().map.iter() {
+                let msg = crate::net::serialization::encode(35, minimap_data(bevy, id, entity, &points)).unwrap();
+                broadcast!(to, rt_handle, player_connections, id, msg);
+            }
+        }
+
         // Get the PlayerPositions.
         let positions = bevy.resource::<PlayerPositions>().0.clone();
         let hits = std::mem::take(&mut bevy.resource_mut::<HitEvents>().0);
@@ -495,6 +833,7 @@ impl World {
                                     wood:  res.0,
                                     stone: res.1,
                                     food:  res.2,
+                                    gold:  res.3,
                                 },
                             )
                             .unwrap();
@@ -506,44 +845,148 @@ impl World {
             }
         }
 
-        let player_map = bevy.resource::<PlayerMap>();
-        let mut updates = Vec::new();
-        for (i, &entity) in player_map.map.iter() {
-            if let (Some(name), Some(pos), Some(aim)) = (
-                bevy.get::<Name>(entity),
-                bevy.get::<Position>(entity),
-                bevy.get::<AimDir>(entity),
-            ) {
-                updates.push(PlayerTO {
-                    id:           *i,
-                    name:         name.0.clone(),
-                    x:            pos.0,
-                    y:            pos.1,
-                    aim:          aim.0,
-                    weapon_index: Some(0),
-                });
+        let progression_updates = std::mem::take(&mut bevy.resource_mut::<ProgressionUpdates>().0);
+        let health_updates = std::mem::take(&mut bevy.resource_mut::<HealthUpdates>().0);
+        let resource_updates = std::mem::take(&mut bevy.resource_mut::<ResourceUpdates>().0);
+
+        if !health_updates.is_empty() || !resource_updates.is_empty() || !progression_updates.is_empty() {
+            let player_map = bevy.resource::<PlayerMap>();
+            let entity_to_id: std::collections::HashMap<Entity, u32> =
+                player_map.map.iter().map(|e| (*e.1, *e.0)).collect();
+
+            // Tell nearby players about the new health.
+            for (entity, new_health) in health_updates {
+                if let (Some(&player_id), Some(pos)) = (entity_to_id.get(&entity), bevy.get::<Position>(entity)) {
+                    let msg =
+                        crate::net::serialization::encode(8, UpdateHealthData { id: player_id, new_health }).unwrap();
+                    let origin = (pos.0, pos.1);
+
+                    broadcast!(nearby, rt_handle, player_connections, positions, origin, 2048.0, msg);
+                }
             }
-        }
 
-        let mut animal_updates = Vec::new();
-        let mut animal_query = bevy.query::<(Entity, &Position, &AnimalType)>();
-        let iterator = animal_query.iter(&self.bevy_world);
-        for (entity, pos, _type) in iterator {
-            animal_updates.push(AnimalTO {
-                id:          entity.index(),
-                x:           pos.0,
-                y:           pos.1,
-                animal_type: *_type as u8,
-            })
-        }
+            // Tell the players about their XP, age and the upgrades they can choose.
+            for entity in progression_updates {
+                let Some(&player_id) = entity_to_id.get(&entity) else {
+                    continue;
+                };
+
+                if let (Some(xp), Some(age), Some(upgrades)) =
+                    (bevy.get::<Xp>(entity), bevy.get::<Age>(entity), bevy.get::<Upgrades>(entity))
+                {
+                    let age_msg = crate::net::serialization::encode(
+                        13,
+                        UpdateAgeData {
+                            xp:        xp.0,
+                            xp_needed: xp.1,
+                            age:       age.0,
+                        },
+                    )
+                    .unwrap();
+
+                    broadcast!(to, rt_handle, player_connections, player_id, age_msg);
+
+                    let options = if upgrades.0 > 0 {
+                        available_upgrades(age.0, &upgrades.1)
+                    } else {
+                        Vec::new()
+                    };
+                    let options_msg = crate::net::serialization::encode(
+                        14,
+                        UpgradeOptionsData {
+                            points:   upgrades.0,
+                            upgrades: options,
+                        },
+                    )
+                    .unwrap();
 
-        if !updates.is_empty() {
-            let update_msg = crate::net::serialization::encode(3, UpdatePlayerData { players: updates }).unwrap();
+                    broadcast!(reliable_to, rt_handle, player_connections, player_id, options_msg);
+                }
+            }
 
-            broadcast!(rt_handle, player_connections, update_msg);
+            // Tell the players about their new resources.
+            for entity in resource_updates {
+                if let (Some(&player_id), Some(res)) = (entity_to_id.get(&entity), bevy.get::<Resources>(entity)) {
+                    let msg = crate::net::serialization::encode(
+                        11,
+                        SetResourceData {
+                            wood:  res.0,
+                            stone: res.1,
+                            food:  res.2,
+                            gold:  res.3,
+                        },
+                    )
+                    .unwrap();
+
+                    broadcast!(to, rt_handle, player_connections, player_id, msg);
+                }
+            }
         }
 
-        if !animal_updates.is_empty() {
+        // Every player is sent the players and animals in their view, looked up in
+        // the spatial index.
+        let player_map = bevy.resource::<PlayerMap>();
+        let index = bevy.resource::<SpatialIndex>();
+        let entity_to_id: HashMap<Entity, u32> = player_map.map.iter().map(|e| (*e.1, *e.0)).collect();
+
+        let mut in_view = Vec::with_capacity(256);
+        for (&id, &entity) in player_map.map.iter() {
+            let Some(pos) = bevy.get::<Position>(entity) else {
+                continue;
+            };
+
+            in_view.clear();
+            index.query_rect(pos.0, pos.1, VIEW_HALF_WIDTH, VIEW_HALF_HEIGHT, &mut in_view);
+
+            let mut updates = Vec::new();
+            let mut animal_updates = Vec::new();
+            for entry in in_view.iter().map(|&i| &index.entries[i]) {
+                match entry.kind {
+                    EntryKind::Player => {
+                        let Some(&other_id) = entity_to_id.get(&entry.entity) else {
+                            continue;
+                        };
+
+                        if let (Some(name), Some(pos), Some(aim)) = (
+                            bevy.get::<Name>(entry.entity),
+                            bevy.get::<Position>(entry.entity),
+                            bevy.get::<AimDir>(entry.entity),
+                        ) {
+                            updates.push(PlayerTO {
+                                id:           other_id,
+                                name:         name.0.clone(),
+                                x:            pos.0,
+                                y:            pos.1,
+                                aim:          aim.0,
+                                weapon_index: bevy.get::<Weapon>(entry.entity).map(|w| *w as u8),
+                                hat:          bevy.get::<Equipment>(entry.entity).and_then(|e| e.0),
+                                accessory:    bevy.get::<Equipment>(entry.entity).and_then(|e| e.1),
+                            });
+                        }
+                    }
+
+                    EntryKind::Animal(animal_type) => {
+                        if let Some(pos) = bevy.get::<Position>(entry.entity) {
+                            animal_updates.push(AnimalTO {
+                                id:          entry.entity.index(),
+                                x:           pos.0,
+                                y:           pos.1,
+                                animal_type: animal_type as u8,
+                            });
+                        }
+                    }
+
+                    _ => {}
+                }
+            }
+
+            if !updates.is_empty() {
+                let update_msg = crate::net::serialization::encode(3, UpdatePlayerData { players: updates }).unwrap();
+
+                broadcast!(to, rt_handle, player_connections, id, update_msg);
+            }
+
+            // Sent even when empty, the client replaces its animals with the list.
             let update_msg = crate::net::serialization::encode(
                 4,
                 AddAnimalData {
@@ -552,8 +995,67 @@ impl World {
             )
             .unwrap();
 
-            // broadcast!(rt_handle, player_connections, update_msg);
-            broadcast!(reliable, rt_handle, player_connections, update_msg);
+            broadcast!(reliable_to, rt_handle, player_connections, id, update_msg);
+        }
+
+        // Act on the cheats caught this tick.
+        handle_violations(bevy, rt_handle, player_connections);
+
+        // Warn, then disconnect, the idle players.
+        check_idle(bevy, rt_handle, player_connections);
+
+        // Announce the players that died, show them their death screen and remove them.
+        // They stay connected and can spawn again.
+        let deaths = std::mem::take(&mut bevy.resource_mut::<Deaths>().0);
+        for victim in deaths {
+            let Some(victim_id) = entity_to_id.get(&victim).copied() else {
+                continue;
+            };
+            let (Some(last_damage), Some(res), Some(age), Some(xp)) = (
+                bevy.get::<LastDamage>(victim).copied(),
+                bevy.get::<Resources>(victim).copied(),
+                bevy.get::<Age>(victim).copied(),
+                bevy.get::<Xp>(victim).copied(),
+            ) else {
+                continue;
+            };
+
+            // Only players are named as killers, animals and biomes are causes.
+            let LastDamage(source, cause) = last_damage;
+            let killer = source.and_then(|e| entity_to_id.get(&e).copied());
+            let killer_name = source.and_then(|e| bevy.get::<Name>(e)).map(|n| n.0.clone());
+
+            let feed = crate::net::serialization::encode(
+                32,
+                KillFeedData {
+                    killer,
+                    victim: victim_id,
+                    cause: cause.id(),
+                    weapon: cause.weapon().map(|w| w as u8),
+                },
+            )
+            .unwrap();
+            broadcast!(reliable, rt_handle, player_connections, feed);
+
+            let death = crate::net::serialization::encode(
+                33,
+                DeathData {
+                    killer,
+                    killer_name: killer.and(killer_name),
+                    cause: cause.id(),
+                    weapon: cause.weapon().map(|w| w as u8),
+                    age: age.0,
+                    xp: total_xp(&age, &xp),
+                    kills: res.4,
+                    gold: res.3,
+                },
+            )
+            .unwrap();
+            broadcast!(reliable_to, rt_handle, player_connections, victim_id, death);
+
+            tracing::info!("player {} died to {:?} (killer {:?})", victim_id, cause, killer);
+            bevy.resource_mut::<PlayerMap>().map.remove(&victim_id);
+            bevy.despawn(victim);
         }
     }
 
@@ -564,46 +1066,63 @@ impl World {
         player_connections: IDToConnection,
         rt_handle: tokio::runtime::Handle,
     ) {
-        let tick = Duration::from_millis(67);
+        let tick = Duration::from_millis(TICK_MS);
         loop {
             let start = std::time::Instant::now();
 
-            {
-                let mut w = world.lock();
-                while let Ok((id, msg)) = input_rx.try_recv() {
-                    w.handle_internal_game_msgs(msg, &input_map, id, &player_connections, &rt_handle);
-                }
+            world.lock().tick(
+                std::iter::from_fn(|| input_rx.try_recv().ok()),
+                &input_map,
+                &player_connections,
+                &rt_handle,
+            );
 
-                {
-                    // let bevy = &mut w.bevy_world;
-                    let World {
-                        ref mut bevy_world,
-                        ref mut schedule,
-                        ..
-                    } = *w;
-
-                    for entry in input_map.iter() {
-                        let id = *entry.key();
-                        if let Some(&entity) = bevy_world.resource::<PlayerMap>().map.get(&id) {
-                            if let Some(mut md) = bevy_world.get_mut::<MoveDir>(entity) {
-                                md.0 = entry.get_move();
-                            }
-                            if let Some(mut ad) = bevy_world.get_mut::<AimDir>(entity) {
-                                ad.0 = entry.get_aim();
-                            }
-                        }
-                    }
+            if let Some(remaining) = tick.checked_sub(start.elapsed()) {
+                std::thread::sleep(remaining);
+            }
+        }
+    }
 
-                    schedule.run(bevy_world);
+    /// Runs a tick. Handles the messages of the players, applies their inputs,
+    /// runs the systems and sends the new state.
+    pub fn tick(
+        &mut self,
+        messages: impl Iterator<Item = (u32, InternalGameMessages)>,
+        input_map: &InputMap,
+        player_connections: &IDToConnection,
+        rt_handle: &tokio::runtime::Handle,
+    ) {
+        for (id, msg) in messages {
+            self.handle_internal_game_msgs(msg, input_map, id, player_connections, rt_handle);
+        }
 
-                    w.broadcast_state(&rt_handle, &player_connections);
+        {
+            // let bevy = &mut w.bevy_world;
+            let World {
+                ref mut bevy_world,
+                ref mut schedule,
+                ..
+            } = *self;
+
+            for entry in input_map.iter() {
+                let id = *entry.key();
+                if entry.take_active() {
+                    touch(bevy_world, id);
+                }
+                if let Some(&entity) = bevy_world.resource::<PlayerMap>().map.get(&id) {
+                    if let Some(mut md) = bevy_world.get_mut::<MoveDir>(entity) {
+                        md.0 = entry.get_move();
+                    }
+                    if let Some(mut ad) = bevy_world.get_mut::<AimDir>(entity) {
+                        ad.0 = entry.get_aim();
+                    }
                 }
             }
 
-            if let Some(remaining) = tick.checked_sub(start.elapsed()) {
-                std::thread::sleep(remaining);
-            }
+            schedule.run(bevy_world);
         }
+
+        self.broadcast_state(rt_handle, player_connections);
     }
 }
 
@@ -623,6 +1142,8 @@ pub struct PlayerInput {
     pub aim_dir:  AtomicU32,
 
     pub has_move: AtomicBool,
+    // Set when the input changes, clients resending the same input are still idle.
+    pub active:   AtomicBool,
 }
 
 use std::sync::atomic::Ordering;
@@ -630,16 +1151,35 @@ impl PlayerInput {
     pub fn new() -> Self {
         Self {
             has_move: AtomicBool::new(false),
+            active:   AtomicBool::new(false),
             move_dir: AtomicU32::new(0),
             aim_dir:  AtomicU32::new(0),
         }
     }
-    pub fn set_move(&self, dir: Option<f32>) {
+    /// Stores the direction, unless it isn't a number. Returns whether it was
+    /// stored.
+    pub fn set_move(&self, dir: Option<f32>) -> bool {
+        if dir.is_some_and(|d| !d.is_finite()) {
+            return false;
+        }
+        if self.get_move() != dir {
+            self.active.store(true, Ordering::Relaxed);
+        }
         self.has_move.store(dir.is_some(), Ordering::Relaxed);
         self.move_dir.store(dir.unwrap_or(0.0).to_bits(), Ordering::Relaxed);
+        true
     }
-    pub fn set_aim(&self, dir: f32) {
+    /// Stores the direction, unless it isn't a number. Returns whether it was
+    /// stored.
+    pub fn set_aim(&self, dir: f32) -> bool {
+        if !dir.is_finite() {
+            return false;
+        }
+        if self.get_aim() != dir {
+            self.active.store(true, Ordering::Relaxed);
+        }
         self.aim_dir.store(dir.to_bits(), Ordering::Relaxed);
+        true
     }
     pub fn get_move(&self) -> Option<f32> {
         self.has_move
@@ -649,6 +1189,10 @@ impl PlayerInput {
     pub fn get_aim(&self) -> f32 {
         f32::from_bits(self.aim_dir.load(Ordering::Relaxed))
     }
+    /// Returns whether the input changed since the last call.
+    pub fn take_active(&self) -> bool {
+        self.active.swap(false, Ordering::Relaxed)
+    }
 }
 
 pub type InputMap = Arc<DashMap<u32, PlayerInput>>;
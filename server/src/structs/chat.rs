@@ -0,0 +1,52 @@
+use bevy_ecs::{entity::Entity, world::World};
+
+use crate::{
+    errors::ClientProducedError,
+    structs::{clock::WorldClock, components::ChatLimit},
+    CONFIG,
+};
+
+/// Censors the profanity in the text, used for names and chat messages.
+pub fn censor(text: &str) -> String {
+    rustrict::Censor::from_str(text)
+        .with_censor_first_character_threshold(rustrict::Type::OFFENSIVE & rustrict::Type::SEXUAL)
+        .with_ignore_false_positives(false)
+        .with_censor_replacement('*')
+        .censor()
+}
+
+/// Validates the chat message of the player against the length and rate
+/// limits. Returns the censored message to deliver.
+pub fn accept_chat(world: &mut World, entity: Entity, text: &str) -> Result<String, ClientProducedError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(ClientProducedError::InvalidChat("empty message"));
+    }
+    if text.chars().count() > CONFIG.chat.max_length as usize {
+        return Err(ClientProducedError::InvalidChat("message too long"));
+    }
+
+    check_chat_rate(world, entity)?;
+
+    Ok(censor(text))
+}
+
+/// Counts a message, or a command, against the rate limit of the player.
+pub fn check_chat_rate(world: &mut World, entity: Entity) -> Result<(), ClientProducedError> {
+    let now = world.resource::<WorldClock>().elapsed_ms();
+    let mut limit = world
+        .get_mut::<ChatLimit>(entity)
+        .ok_or(ClientProducedError::InvalidChat("not a player"))?;
+
+    // Messages are counted over fixed windows.
+    if now.saturating_sub(limit.0) >= CONFIG.chat.rate_window as u64 {
+        limit.0 = now;
+        limit.1 = 0;
+    }
+    if limit.1 >= CONFIG.chat.rate_limit {
+        return Err(ClientProducedError::InvalidChat("rate limited"));
+    }
+    limit.1 += 1;
+
+    Ok(())
+}
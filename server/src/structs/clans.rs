@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+
+use bevy_ecs::{entity::Entity, resource::Resource, world::World};
+use shared::to_client::{ClanTO, ClansData};
+
+use crate::{
+    errors::ClientProducedError,
+    structs::{bevy::PlayerMap, chat::censor, components::ClanId},
+    CONFIG,
+};
+
+/// A clan, its members are player ids.
+#[derive(Clone, Debug)]
+pub struct Clan {
+    pub name:     String,
+    pub owner:    u32,
+    // The owner included.
+    pub members:  Vec<u32>,
+    // Players waiting for the owner to accept them.
+    pub requests: Vec<u32>,
+}
+
+/// Every clan, by id.
+#[derive(Resource, Default, Debug)]
+pub struct Clans {
+    pub clans: HashMap<u32, Clan>,
+    next_id:   u32,
+}
+
+impl Clans {
+    /// Returns the clan owned by the player.
+    fn owned_by(&mut self, owner: u32) -> Result<(u32, &mut Clan), ClientProducedError> {
+        self.clans
+            .iter_mut()
+            .find(|(_, clan)| clan.owner == owner)
+            .map(|(&id, clan)| (id, clan))
+            .ok_or(ClientProducedError::InvalidClanAction("not a clan owner"))
+    }
+
+    /// Returns the id of the clan the player is a member of.
+    pub fn clan_of(&self, id: u32) -> Option<u32> {
+        self.clans
+            .iter()
+            .find(|(_, clan)| clan.members.contains(&id))
+            .map(|(&clan_id, _)| clan_id)
+    }
+
+    /// Returns the clans as sent to the clients.
+    pub fn to_data(&self) -> ClansData {
+        ClansData {
+            clans: self
+                .clans
+                .iter()
+                .map(|(&id, clan)| ClanTO {
+                    id,
+                    name: clan.name.clone(),
+                    owner: clan.owner,
+                    members: clan.members.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Returns whether the two clan memberships make allies.
+pub fn are_allies(a: Option<&ClanId>, b: Option<&ClanId>) -> bool {
+    matches!((a, b), (Some(a), Some(b)) if a == b)
+}
+
+/// Returns the entity of the player with the id.
+fn player(world: &World, id: u32) -> Result<Entity, ClientProducedError> {
+    world
+        .resource::<PlayerMap>()
+        .map
+        .get(&id)
+        .copied()
+        .ok_or(ClientProducedError::InvalidClanAction("no such player"))
+}
+
+/// Creates a clan owned by the player. Returns the id of the clan.
+pub fn create_clan(world: &mut World, id: u32, name: &str) -> Result<u32, ClientProducedError> {
+    let entity = player(world, id)?;
+    if world.get::<ClanId>(entity).is_some() {
+        return Err(ClientProducedError::InvalidClanAction("already in a clan"));
+    }
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ClientProducedError::InvalidClanAction("empty name"));
+    }
+    if name.chars().count() > CONFIG.clans.max_name_length as usize {
+        return Err(ClientProducedError::InvalidClanAction("name too long"));
+    }
+    let name = censor(name);
+
+    let mut clans = world.resource_mut::<Clans>();
+    if clans.clans.values().any(|c| c.name.eq_ignore_ascii_case(&name)) {
+        return Err(ClientProducedError::InvalidClanAction("name taken"));
+    }
+
+    // The player no longer waits on other clans.
+    for clan in clans.clans.values_mut() {
+        clan.requests.retain(|&r| r != id);
+    }
+
+    clans.next_id += 1;
+    let clan_id = clans.next_id;
+    clans.clans.insert(
+        clan_id,
+        Clan {
+            name,
+            owner: id,
+            members: vec![id],
+            requests: Vec::new(),
+        },
+    );
+
+    world.entity_mut(entity).insert(ClanId(clan_id));
+    Ok(clan_id)
+}
+
+/// Asks to join the clan. Returns the id of the owner, who decides.
+pub fn request_join(world: &mut World, id: u32, clan_id: u32) -> Result<u32, ClientProducedError> {
+    let entity = player(world, id)?;
+    if world.get::<ClanId>(entity).is_some() {
+        return Err(ClientProducedError::InvalidClanAction("already in a clan"));
+    }
+
+    let mut clans = world.resource_mut::<Clans>();
+    let clan = clans
+        .clans
+        .get_mut(&clan_id)
+        .ok_or(ClientProducedError::InvalidClanAction("unknown clan"))?;
+    if clan.members.len() >= CONFIG.clans.max_members as usize {
+        return Err(ClientProducedError::InvalidClanAction("clan full"));
+    }
+    if clan.requests.contains(&id) {
+        return Err(ClientProducedError::InvalidClanAction("already requested"));
+    }
+
+    clan.requests.push(id);
+    Ok(clan.owner)
+}
+
+/// Accepts the join request of the player into the clan of the owner.
+pub fn accept_member(world: &mut World, owner: u32, id: u32) -> Result<(), ClientProducedError> {
+    let entity = player(world, id)?;
+    // They may have joined another clan in the meantime.
+    let in_clan = world.get::<ClanId>(entity).is_some();
+
+    let mut clans = world.resource_mut::<Clans>();
+    let (clan_id, clan) = clans.owned_by(owner)?;
+    if !clan.requests.contains(&id) {
+        return Err(ClientProducedError::InvalidClanAction("no join request"));
+    }
+    clan.requests.retain(|&r| r != id);
+
+    if in_clan {
+        return Err(ClientProducedError::InvalidClanAction("already in a clan"));
+    }
+    if clan.members.len() >= CONFIG.clans.max_members as usize {
+        return Err(ClientProducedError::InvalidClanAction("clan full"));
+    }
+
+    clan.members.push(id);
+    world.entity_mut(entity).insert(ClanId(clan_id));
+    Ok(())
+}
+
+/// Removes the player from the clan of the owner.
+pub fn kick_member(world: &mut World, owner: u32, id: u32) -> Result<(), ClientProducedError> {
+    if owner == id {
+        return Err(ClientProducedError::InvalidClanAction("owners leave instead"));
+    }
+
+    let mut clans = world.resource_mut::<Clans>();
+    let (_, clan) = clans.owned_by(owner)?;
+    if !clan.members.contains(&id) {
+        return Err(ClientProducedError::InvalidClanAction("not a member"));
+    }
+    clan.members.retain(|&m| m != id);
+
+    if let Ok(entity) = player(world, id) {
+        world.entity_mut(entity).remove::<ClanId>();
+    }
+    Ok(())
+}
+
+/// Removes the player from their clan, and from every join request. The clan is
+/// disbanded when its owner leaves. Returns whether any clan changed.
+pub fn leave_clan(world: &mut World, id: u32) -> bool {
+    let mut clans = world.resource_mut::<Clans>();
+
+    let mut changed = false;
+    for clan in clans.clans.values_mut() {
+        let requests = clan.requests.len();
+        clan.requests.retain(|&r| r != id);
+        changed |= clan.requests.len() != requests;
+    }
+
+    let Some((&clan_id, clan)) = clans.clans.iter_mut().find(|(_, c)| c.members.contains(&id)) else {
+        return changed;
+    };
+
+    // Everyone leaves with the owner.
+    let left = if clan.owner == id {
+        clans.clans.remove(&clan_id).map(|c| c.members).unwrap_or_default()
+    } else {
+        clan.members.retain(|&m| m != id);
+        vec![id]
+    };
+
+    for member in left {
+        if let Ok(entity) = player(world, member) {
+            world.entity_mut(entity).remove::<ClanId>();
+        }
+    }
+    true
+}
+
+/// Returns a description of the clan of the player, for '/team'.
+pub fn describe_clan(world: &World, entity: Entity) -> Option<String> {
+    let clan_id = world.get::<ClanId>(entity)?;
+    let clan = world.resource::<Clans>().clans.get(&clan_id.0)?;
+
+    Some(format!(
+        "clan {} (id {}), {}/{} members, owner {}, {} pending requests",
+        clan.name,
+        clan_id.0,
+        clan.members.len(),
+        CONFIG.clans.max_members,
+        clan.owner,
+        clan.requests.len()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a world with the players with the ids 1 to 'n' alive.
+    fn world_with_players(n: u32) -> World {
+        let mut world = World::new();
+        world.insert_resource(PlayerMap::default());
+        world.insert_resource(Clans::default());
+        for id in 1..=n {
+            let entity = world.spawn_empty().id();
+            world.resource_mut::<PlayerMap>().map.insert(id, entity);
+        }
+        world
+    }
+
+    fn clan_id(world: &World, id: u32) -> Option<u32> {
+        world.get::<ClanId>(player(world, id).unwrap()).map(|c| c.0)
+    }
+
+    fn is_invalid(result: Result<impl Sized, ClientProducedError>, reason: &str) -> bool {
+        matches!(result, Err(ClientProducedError::InvalidClanAction(r)) if r == reason)
+    }
+
+    #[test]
+    fn owner_accepts_requests() {
+        let mut world = world_with_players(2);
+        let clan = create_clan(&mut world, 1, "wolves").unwrap();
+
+        assert_eq!(request_join(&mut world, 2, clan).unwrap(), 1);
+        accept_member(&mut world, 1, 2).unwrap();
+
+        assert_eq!(clan_id(&world, 2), Some(clan));
+        assert_eq!(world.resource::<Clans>().clans[&clan].members, vec![1, 2]);
+        assert!(world.resource::<Clans>().clans[&clan].requests.is_empty());
+    }
+
+    #[test]
+    fn rejects_accepting_without_request() {
+        let mut world = world_with_players(2);
+        create_clan(&mut world, 1, "wolves").unwrap();
+
+        assert!(is_invalid(accept_member(&mut world, 1, 2), "no join request"));
+        assert_eq!(clan_id(&world, 2), None);
+    }
+
+    #[test]
+    fn only_owners_accept_and_kick() {
+        let mut world = world_with_players(3);
+        let clan = create_clan(&mut world, 1, "wolves").unwrap();
+        request_join(&mut world, 2, clan).unwrap();
+        accept_member(&mut world, 1, 2).unwrap();
+        request_join(&mut world, 3, clan).unwrap();
+
+        assert!(is_invalid(accept_member(&mut world, 2, 3), "not a clan owner"));
+        assert!(is_invalid(kick_member(&mut world, 3, 2), "not a clan owner"));
+        assert!(is_invalid(kick_member(&mut world, 1, 1), "owners leave instead"));
+
+        kick_member(&mut world, 1, 2).unwrap();
+        assert_eq!(clan_id(&world, 2), None);
+        assert_eq!(world.resource::<Clans>().clans[&clan].members, vec![1]);
+    }
+
+    #[test]
+    fn rejects_joining_while_in_a_clan() {
+        let mut world = world_with_players(2);
+        let wolves = create_clan(&mut world, 1, "wolves").unwrap();
+        let bears = create_clan(&mut world, 2, "bears").unwrap();
+
+        assert!(is_invalid(request_join(&mut world, 2, wolves), "already in a clan"));
+        assert!(is_invalid(create_clan(&mut world, 1, "foxes"), "already in a clan"));
+        assert_eq!(clan_id(&world, 2), Some(bears));
+    }
+
+    #[test]
+    fn rejects_accepting_players_who_joined_elsewhere() {
+        let mut world = world_with_players(2);
+        let wolves = create_clan(&mut world, 1, "wolves").unwrap();
+        request_join(&mut world, 2, wolves).unwrap();
+        let bears = create_clan(&mut world, 2, "bears").unwrap();
+
+        // Creating a clan dropped the request.
+        assert!(is_invalid(accept_member(&mut world, 1, 2), "no join request"));
+        assert_eq!(clan_id(&world, 2), Some(bears));
+    }
+
+    #[test]
+    fn owner_leaving_disbands_the_clan() {
+        let mut world = world_with_players(3);
+        let clan = create_clan(&mut world, 1, "wolves").unwrap();
+        request_join(&mut world, 2, clan).unwrap();
+        accept_member(&mut world, 1, 2).unwrap();
+        request_join(&mut world, 3, clan).unwrap();
+
+        assert!(leave_clan(&mut world, 1));
+
+        assert!(world.resource::<Clans>().clans.is_empty());
+        assert_eq!(clan_id(&world, 1), None);
+        assert_eq!(clan_id(&world, 2), None);
+        // The name is free again.
+        assert!(create_clan(&mut world, 3, "wolves").is_ok());
+    }
+
+    #[test]
+    fn members_leave_alone() {
+        let mut world = world_with_players(2);
+        let clan = create_clan(&mut world, 1, "wolves").unwrap();
+        request_join(&mut world, 2, clan).unwrap();
+        accept_member(&mut world, 1, 2).unwrap();
+
+        assert!(leave_clan(&mut world, 2));
+        assert_eq!(clan_id(&world, 1), Some(clan));
+        assert_eq!(clan_id(&world, 2), None);
+        assert!(!leave_clan(&mut world, 2));
+    }
+
+    #[test]
+    fn rejects_taken_names_in_any_case() {
+        let mut world = world_with_players(2);
+        create_clan(&mut world, 1, "Wolves").unwrap();
+
+        assert!(is_invalid(create_clan(&mut world, 2, "wOLVES"), "name taken"));
+        assert!(is_invalid(create_clan(&mut world, 2, "  wolves "), "name taken"));
+        assert!(create_clan(&mut world, 2, "wolves2").is_ok());
+    }
+}
@@ -0,0 +1,57 @@
+use bevy_ecs::resource::Resource;
+use shared::to_client::TimeSyncData;
+
+use crate::CONFIG;
+
+// Length of a tick, in ms.
+pub const TICK_MS: u64 = 67;
+
+/// The world clock. Advances by one every tick, the day and the time of day are
+/// derived from it and the configured day length.
+#[derive(Resource, Default, Clone, Copy, Debug)]
+pub struct WorldClock {
+    pub ticks: u64,
+}
+
+impl WorldClock {
+    /// Returns the game time elapsed, in ms.
+    pub fn elapsed_ms(&self) -> u64 {
+        self.ticks * TICK_MS
+    }
+
+    /// Returns the current day, starting from 0.
+    pub fn day(&self) -> u32 {
+        (self.elapsed_ms() / CONFIG.time.day_length as u64) as u32
+    }
+
+    /// Returns the time of day, from 0 (dawn) to 1.
+    pub fn time_of_day(&self) -> f32 {
+        let day_length = CONFIG.time.day_length as u64;
+        (self.elapsed_ms() % day_length) as f32 / day_length as f32
+    }
+
+    /// Returns true during the night, the last part of every day.
+    pub fn is_night(&self) -> bool {
+        self.time_of_day() >= 1. - CONFIG.time.night_length
+    }
+
+    /// Returns true once every interval, in ms.
+    pub fn every(&self, interval: u32) -> bool {
+        self.elapsed_ms() % (interval as u64) < TICK_MS
+    }
+
+    /// Returns true on the ticks the clients should be told about the time.
+    pub fn sync_due(&self) -> bool {
+        self.every(CONFIG.time.sync_interval)
+    }
+
+    /// Returns the time sync packet data of the current time.
+    pub fn sync_data(&self) -> TimeSyncData {
+        TimeSyncData {
+            day:          self.day(),
+            time:         self.time_of_day(),
+            day_length:   CONFIG.time.day_length,
+            night_length: CONFIG.time.night_length,
+        }
+    }
+}
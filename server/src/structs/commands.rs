@@ -0,0 +1,293 @@
+use bevy_ecs::entity::Entity;
+use shared::to_client::SystemMessageData;
+use wtransport::VarInt;
+
+use crate::{
+    broadcast,
+    errors::CommandError,
+    structs::{
+        bevy::{BanList, IDToConnection, PlayerMap, World},
+        clans::describe_clan,
+        components::{Admin, Age, LastPosition, Position, ResourceUpdates, Resources, Xp},
+    },
+    CONFIG,
+};
+
+/// Everything a command can act on.
+pub struct CommandContext<'a> {
+    pub world:       &'a mut bevy_ecs::world::World,
+    pub bans:        &'a BanList,
+    pub connections: &'a IDToConnection,
+    pub rt_handle:   &'a tokio::runtime::Handle,
+    // The player running the command.
+    pub id:          u32,
+    pub entity:      Entity,
+}
+
+impl CommandContext<'_> {
+    /// Returns the entity of the player with the id.
+    fn player(&self, id: u32) -> Result<Entity, CommandError> {
+        self.world
+            .resource::<PlayerMap>()
+            .map
+            .get(&id)
+            .copied()
+            .ok_or(CommandError::NoPlayer(id))
+    }
+
+    /// Closes the connection of the player, they are removed from the world
+    /// once it is gone.
+    fn disconnect(&self, id: u32, reason: &[u8]) {
+        if let Some(conn) = self.connections.get(&id) {
+            conn.connection.close(VarInt::from_u32(2), reason);
+        }
+    }
+}
+
+/// The reply shown to the player running the command.
+pub type CommandResult = Result<String, CommandError>;
+
+/// An entry of the command registry.
+pub struct Command {
+    pub name:        &'static str,
+    pub usage:       &'static str,
+    pub description: &'static str,
+    // Only admins can run it.
+    pub admin:       bool,
+    pub run:         fn(&mut CommandContext, &[&str]) -> CommandResult,
+}
+
+/// The command registry. New commands only need an entry here.
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name:        "help",
+        usage:       "/help",
+        description: "lists the commands",
+        admin:       false,
+        run:         help,
+    },
+    Command {
+        name:        "ping",
+        usage:       "/ping",
+        description: "checks the connection",
+        admin:       false,
+        run:         |_, _| Ok("pong".to_string()),
+    },
+    Command {
+        name:        "stats",
+        usage:       "/stats",
+        description: "shows your stats",
+        admin:       false,
+        run:         stats,
+    },
+    Command {
+        name:        "team",
+        usage:       "/team",
+        description: "shows your clan",
+        admin:       false,
+        run:         team,
+    },
+    Command {
+        name:        "auth",
+        usage:       "/auth <token>",
+        description: "authorizes you as an admin",
+        admin:       false,
+        run:         auth,
+    },
+    Command {
+        name:        "kick",
+        usage:       "/kick <id>",
+        description: "disconnects a player",
+        admin:       true,
+        run:         kick,
+    },
+    Command {
+        name:        "ban",
+        usage:       "/ban <id>",
+        description: "disconnects a player and bans their address",
+        admin:       true,
+        run:         ban,
+    },
+    Command {
+        name:        "tp",
+        usage:       "/tp <id> | /tp <x> <y>",
+        description: "teleports you to a player or a position",
+        admin:       true,
+        run:         tp,
+    },
+    Command {
+        name:        "give",
+        usage:       "/give <wood|stone|food|gold|xp> <amount> [id]",
+        description: "gives resources to you or a player",
+        admin:       true,
+        run:         give,
+    },
+    Command {
+        name:        "announce",
+        usage:       "/announce <message>",
+        description: "sends a message to every player",
+        admin:       true,
+        run:         announce,
+    },
+];
+
+/// Returns the command with the name.
+pub fn get_command(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|c| c.name.eq_ignore_ascii_case(name))
+}
+
+/// Parses and runs the command, given without its leading '/'.
+pub fn run_command(ctx: &mut CommandContext, text: &str) -> CommandResult {
+    let mut args = text.split_whitespace();
+    let command = args.next().and_then(get_command).ok_or(CommandError::Unknown)?;
+
+    if command.admin && !ctx.world.entity(ctx.entity).contains::<Admin>() {
+        return Err(CommandError::NotAllowed);
+    }
+
+    let args: Vec<&str> = args.collect();
+    (command.run)(ctx, &args)
+}
+
+/// Parses the argument, or fails with the usage of the command.
+fn parse<T: std::str::FromStr>(arg: Option<&&str>, usage: &'static str) -> Result<T, CommandError> {
+    arg.and_then(|a| a.parse().ok()).ok_or(CommandError::Usage(usage))
+}
+
+fn help(ctx: &mut CommandContext, _: &[&str]) -> CommandResult {
+    let admin = ctx.world.entity(ctx.entity).contains::<Admin>();
+    let lines: Vec<String> = COMMANDS
+        .iter()
+        .filter(|c| admin || !c.admin)
+        .map(|c| format!("{} - {}", c.usage, c.description))
+        .collect();
+
+    Ok(lines.join("\n"))
+}
+
+fn stats(ctx: &mut CommandContext, _: &[&str]) -> CommandResult {
+    let player = ctx.world.entity(ctx.entity);
+    let (Some(res), Some(xp), Some(age)) = (player.get::<Resources>(), player.get::<Xp>(), player.get::<Age>()) else {
+        return Err(CommandError::NoPlayer(ctx.id));
+    };
+
+    Ok(format!(
+        "age {} ({}/{} xp), {} kills, {} wood, {} stone, {} food, {} gold",
+        age.0, xp.0, xp.1, res.4, res.0, res.1, res.2, res.3
+    ))
+}
+
+fn team(ctx: &mut CommandContext, _: &[&str]) -> CommandResult {
+    Ok(describe_clan(ctx.world, ctx.entity).unwrap_or_else(|| "you are not in a clan".to_string()))
+}
+
+fn auth(ctx: &mut CommandContext, args: &[&str]) -> CommandResult {
+    let token = args.first().ok_or(CommandError::Usage("/auth <token>"))?;
+    if !CONFIG.admin.tokens.iter().any(|t| t == token) {
+        tracing::warn!("player {} used an invalid admin token", ctx.id);
+        return Err(CommandError::InvalidToken);
+    }
+
+    ctx.world.entity_mut(ctx.entity).insert(Admin);
+    tracing::info!("player {} is now an admin", ctx.id);
+    Ok("you are now an admin".to_string())
+}
+
+fn kick(ctx: &mut CommandContext, args: &[&str]) -> CommandResult {
+    let id = parse(args.first(), "/kick <id>")?;
+    ctx.player(id)?;
+
+    ctx.disconnect(id, b"kicked");
+    tracing::info!("player {} kicked player {}", ctx.id, id);
+    Ok(format!("kicked player {}", id))
+}
+
+fn ban(ctx: &mut CommandContext, args: &[&str]) -> CommandResult {
+    let id = parse(args.first(), "/ban <id>")?;
+    ctx.player(id)?;
+
+    if let Some(conn) = ctx.connections.get(&id) {
+        ctx.bans.insert(conn.connection.remote_address().ip());
+    }
+    ctx.disconnect(id, b"banned");
+    tracing::info!("player {} banned player {}", ctx.id, id);
+    Ok(format!("banned player {}", id))
+}
+
+fn tp(ctx: &mut CommandContext, args: &[&str]) -> CommandResult {
+    const USAGE: &str = "/tp <id> | /tp <x> <y>";
+
+    let target = match args {
+        [id] => {
+            let entity = ctx.player(parse(Some(id), USAGE)?)?;
+            *ctx.world.get::<Position>(entity).ok_or(CommandError::Usage(USAGE))?
+        }
+        [x, y] => {
+            let size = CONFIG.map.size as f32;
+            let x: f32 = parse(Some(x), USAGE)?;
+            let y: f32 = parse(Some(y), USAGE)?;
+            Position(x.clamp(0., size), y.clamp(0., size))
+        }
+        _ => return Err(CommandError::Usage(USAGE)),
+    };
+
+    // The collision system pushes the player out of whatever they land in. The
+    // anti-cheat is told about the jump.
+    if let Some(mut pos) = ctx.world.get_mut::<Position>(ctx.entity) {
+        *pos = target;
+    }
+    if let Some(mut last) = ctx.world.get_mut::<LastPosition>(ctx.entity) {
+        last.0 = target;
+    }
+    Ok(format!("teleported to {:.0}, {:.0}", target.0, target.1))
+}
+
+fn give(ctx: &mut CommandContext, args: &[&str]) -> CommandResult {
+    const USAGE: &str = "/give <wood|stone|food|gold|xp> <amount> [id]";
+
+    let kind = *args.first().ok_or(CommandError::Usage(USAGE))?;
+    let amount: u32 = parse(args.get(1), USAGE)?;
+    let entity = match args.get(2) {
+        Some(id) => ctx.player(parse(Some(id), USAGE)?)?,
+        None => ctx.entity,
+    };
+
+    if kind == "xp" {
+        // The progression system picks up the XP change.
+        let mut xp = ctx.world.get_mut::<Xp>(entity).ok_or(CommandError::Usage(USAGE))?;
+        xp.0 = xp.0.saturating_add(amount);
+    } else {
+        let mut res = ctx
+            .world
+            .get_mut::<Resources>(entity)
+            .ok_or(CommandError::Usage(USAGE))?;
+        match kind {
+            "wood" => res.0 = res.0.saturating_add(amount),
+            "stone" => res.1 = res.1.saturating_add(amount),
+            "food" => res.2 = res.2.saturating_add(amount),
+            "gold" => res.3 = res.3.saturating_add(amount),
+            _ => return Err(CommandError::Usage(USAGE)),
+        }
+        ctx.world.resource_mut::<ResourceUpdates>().0.push(entity);
+    }
+
+    tracing::info!("player {} gave {} {}", ctx.id, amount, kind);
+    Ok(format!("gave {} {}", amount, kind))
+}
+
+fn announce(ctx: &mut CommandContext, args: &[&str]) -> CommandResult {
+    if args.is_empty() {
+        return Err(CommandError::Usage("/announce <message>"));
+    }
+
+    let msg = crate::net::serialization::encode(
+        23,
+        SystemMessageData {
+            message: args.join(" "),
+        },
+    )
+    .unwrap();
+    broadcast!(reliable, ctx.rt_handle, ctx.connections, msg);
+
+    Ok("announced".to_string())
+}
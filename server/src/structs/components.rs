@@ -1,9 +1,10 @@
-use bevy_ecs::{bundle::Bundle, component::Component, entity::Entity};
-use shared::objects::GameObjects;
+use bevy_ecs::{bundle::Bundle, component::Component, entity::Entity, system::Commands};
+use shared::objects::{GameObjects, UsableGameObjects};
 
 use crate::{
-    structs::{bevy, weapons::Weapon},
+    structs::{anticheat::Violation, bevy, store::StoreKind, weapons::Weapon},
     systems::{Collider, NonReactiveCollider, ReactiveCollider},
+    CONFIG,
 };
 
 #[derive(Component, Clone, Copy, Debug)]
@@ -23,13 +24,25 @@ pub struct Wall;
 #[derive(Component)]
 pub struct PlayerEntity;
 
+#[derive(Component)]
+// Players authorized with an admin token.
+pub struct Admin;
+
+#[derive(Component)]
+// Players the anti-cheat flagged to the admins.
+pub struct Flagged;
+
+#[derive(Component)]
+// Players the anti-cheat limits, their attacks don't land.
+pub struct ShadowLimited;
+
 #[derive(Component)]
 pub struct AnimalEntity;
 
 #[derive(Component)]
 pub struct ObjectEntity;
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy, Debug)]
 // wood, stone, berry, gold, kills
 pub struct Resources(pub u32, pub u32, pub u32, pub u32, pub u32);
 
@@ -39,7 +52,7 @@ pub struct MoveDir(pub Option<f32>);
 #[derive(Component)]
 pub struct AimDir(pub f32);
 
-#[derive(Component, Clone, Copy, Debug)]
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AnimalType {
     Wolf,
     Fish,
@@ -70,6 +83,133 @@ pub struct ReloadState(pub u32, pub u32);
 // target, x, y
 pub struct AiTarget(pub Option<Entity>, pub f32, pub f32);
 
+#[derive(Component, Clone, Copy, Debug, Default)]
+// last entity that damaged this one
+pub struct LastHitBy(pub Option<Entity>);
+
+#[derive(Component, Clone, Copy, Debug)]
+// index of the spawn zone the animal belongs to
+pub struct Habitat(pub u8);
+
+#[derive(Component, Clone, Copy, Debug)]
+// current, needed for the next age
+pub struct Xp(pub u32, pub u32);
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Age(pub u8);
+
+#[derive(Component, Clone, Debug, Default)]
+// unspent points, chosen upgrade ids
+pub struct Upgrades(pub u8, pub Vec<u8>);
+
+#[derive(Component, Clone, Copy, Debug, Default)]
+// hat, accessory
+pub struct Equipment(pub Option<u8>, pub Option<u8>);
+
+#[derive(Component, Clone, Debug, Default)]
+// hats, accessories
+pub struct OwnedItems(pub Vec<u8>, pub Vec<u8>);
+
+impl OwnedItems {
+    pub fn get(&self, kind: StoreKind) -> &Vec<u8> {
+        match kind {
+            StoreKind::Hat => &self.0,
+            StoreKind::Accessory => &self.1,
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: StoreKind) -> &mut Vec<u8> {
+        match kind {
+            StoreKind::Hat => &mut self.0,
+            StoreKind::Accessory => &mut self.1,
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug, Default)]
+// requested item
+pub struct UseItemState(pub Option<u8>);
+
+#[derive(Component, Clone, Copy, Debug, Default)]
+// remaining cooldown (ms), indexed by item
+pub struct ItemCooldowns(pub [u32; UsableGameObjects::COUNT]);
+
+#[derive(Component, Clone, Copy, Debug, Default)]
+// start of the rate limit window (ms), messages sent in the window
+pub struct ChatLimit(pub u64, pub u8);
+
+#[derive(Component, Clone, Copy, Debug, Default)]
+// end of the spawn protection (ms), the player can't be damaged before it
+pub struct SpawnProtection(pub u64);
+
+impl SpawnProtection {
+    pub fn active(&self, now: u64) -> bool {
+        now < self.0
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug, Default)]
+// time of the last map ping (ms)
+pub struct LastPing(pub Option<u64>);
+
+#[derive(Component, Clone, Copy, Debug)]
+// position at the end of the last tick
+pub struct LastPosition(pub Position);
+
+#[derive(Component, Clone, Copy, Debug)]
+// where the player spawned, their base on the minimap
+pub struct SpawnPoint(pub Position);
+
+#[derive(Component, Clone, Copy, Debug, Default)]
+// anti-cheat suspicion, time of the last attack started (ms)
+pub struct Suspicion(pub f32, pub Option<u64>);
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+// clan, only on clan members
+pub struct ClanId(pub u32);
+
+#[derive(Component, Clone, Copy, Debug)]
+// player that placed the structure, they and their allies pass through it
+pub struct Owner(pub Entity);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// What dealt damage to a player, shown in the kill feed.
+pub enum DamageCause {
+    #[default]
+    Unknown,
+    Weapon(Weapon),
+    Thorns,
+    Wolf,
+    Lava,
+    Biome,
+}
+
+impl DamageCause {
+    /// Returns the id sent to the clients.
+    pub fn id(&self) -> u8 {
+        match self {
+            DamageCause::Unknown => 0,
+            DamageCause::Weapon(_) => 1,
+            DamageCause::Thorns => 2,
+            DamageCause::Wolf => 3,
+            DamageCause::Lava => 4,
+            DamageCause::Biome => 5,
+        }
+    }
+
+    /// Returns the weapon the damage was dealt with, if any.
+    pub fn weapon(&self) -> Option<Weapon> {
+        match self {
+            DamageCause::Weapon(weapon) => Some(*weapon),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug, Default)]
+// source, cause of the last damage taken while alive
+pub struct LastDamage(pub Option<Entity>, pub DamageCause);
+
 #[derive(Bundle)]
 pub struct PlayerBundle(
     pub PlayerEntity,
@@ -85,6 +225,20 @@ pub struct PlayerBundle(
     pub Resources,
     pub Collider,
     pub ReactiveCollider,
+    pub UseItemState,
+    pub ItemCooldowns,
+    pub Xp,
+    pub Age,
+    pub Upgrades,
+    pub Equipment,
+    pub OwnedItems,
+    pub ChatLimit,
+    pub LastDamage,
+    pub LastPing,
+    pub SpawnProtection,
+    pub LastPosition,
+    pub SpawnPoint,
+    pub Suspicion,
 );
 
 #[derive(Bundle)]
@@ -113,28 +267,33 @@ pub fn spawn_wall(commands: &mut bevy_ecs::world::World, x: f32, y: f32, half_wi
         .id()
 }
 
-pub fn spawn_animal(commands: &mut bevy_ecs::world::World, animal_type: AnimalType, x: f32, y: f32) -> Entity {
-    use crate::systems::{Collider, ReactiveCollider};
+pub fn spawn_animal(commands: &mut Commands, animal_type: AnimalType, habitat: u8, x: f32, y: f32) -> Entity {
+    let (health, state) = match animal_type {
+        AnimalType::Wolf => (80.0, AiState::Idle),
+        AnimalType::Fish => (20.0, AiState::Wander),
+    };
+
+    let mut animal = commands.spawn((
+        Position(x, y),
+        Velocity(0.0, 0.0),
+        AnimalEntity,
+        animal_type,
+        Habitat(habitat),
+        Health(health, health),
+        ReloadState(0, CONFIG.animals.wolf_bite_cooldown),
+        LastHitBy(None),
+        state,
+        AiTarget(None, x, y),
+        Name(format!("{:?}", animal_type)),
+    ));
 
-    let (health, radius) = match animal_type {
-        AnimalType::Wolf => (80.0, 25.0),
-        AnimalType::Fish => (20.0, 10.0),
+    // Fish swim through each other, wolves get pushed around like players.
+    match animal_type {
+        AnimalType::Wolf => animal.insert((Collider::circle(25.0), ReactiveCollider)),
+        AnimalType::Fish => animal.insert((Collider::circle(35.0), NonReactiveCollider)),
     };
 
-    commands
-        .spawn((
-            Position(x, y),
-            Velocity(0.0, 0.0),
-            Collider::circle(radius),
-            ReactiveCollider,
-            AnimalEntity,
-            animal_type,
-            Health(health, health),
-            AiState::Idle,
-            AiTarget(None, x, y),
-            Name(format!("{:?}", animal_type)),
-        ))
-        .id()
+    animal.id()
 }
 
 #[derive(bevy_ecs::prelude::Resource, Default)]
@@ -156,3 +315,27 @@ pub struct PlayerHitEvent {
 
 #[derive(bevy_ecs::prelude::Resource, Default)]
 pub struct PlayerPositions(pub std::collections::HashMap<u32, (f32, f32)>);
+
+#[derive(bevy_ecs::prelude::Resource, Default)]
+// entity, new health
+pub struct HealthUpdates(pub Vec<(Entity, f32)>);
+
+#[derive(bevy_ecs::prelude::Resource, Default)]
+// entities whose resources changed
+pub struct ResourceUpdates(pub Vec<Entity>);
+
+#[derive(bevy_ecs::prelude::Resource, Default)]
+// entities whose XP, age or upgrades changed
+pub struct ProgressionUpdates(pub Vec<Entity>);
+
+#[derive(bevy_ecs::prelude::Resource, Default)]
+// players that died this tick
+pub struct Deaths(pub Vec<Entity>);
+
+#[derive(bevy_ecs::prelude::Resource, Default)]
+// players caught cheating, handled once the tick is over
+pub struct Violations(pub Vec<(Entity, Violation)>);
+
+#[derive(bevy_ecs::prelude::Resource, Default)]
+// spawn zone index, remaining delay (ms)
+pub struct PendingSpawns(pub Vec<(u8, u32)>);
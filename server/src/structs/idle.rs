@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use bevy_ecs::resource::Resource;
+use shared::to_client::IdleWarningData;
+use wtransport::VarInt;
+
+use crate::{
+    broadcast,
+    structs::{
+        bevy::{IDToConnection, World},
+        clock::WorldClock,
+    },
+    CONFIG,
+};
+
+// ms between two checks for idle players.
+const IDLE_CHECK_INTERVAL: u32 = 1000;
+
+/// When a connected player last did something.
+#[derive(Clone, Copy, Debug)]
+pub struct IdleTimer {
+    // Time of their last input (ms).
+    pub last_input: u64,
+    pub warned:     bool,
+}
+
+/// The idle timers of the connected players, by id. Players count from when
+/// they are first seen, dead players and players in the menu included.
+#[derive(Resource, Default, Debug)]
+pub struct IdleTimers(pub HashMap<u32, IdleTimer>);
+
+/// Records an input of the player, they are no longer idle.
+pub fn touch(world: &mut bevy_ecs::world::World, id: u32) {
+    let now = world.resource::<WorldClock>().elapsed_ms();
+    world.resource_mut::<IdleTimers>().0.insert(
+        id,
+        IdleTimer {
+            last_input: now,
+            warned:     false,
+        },
+    );
+}
+
+/// Warns the players idle for too long, and disconnects the ones that stayed
+/// idle after the warning.
+pub fn check_idle(
+    world: &mut bevy_ecs::world::World,
+    rt_handle: &tokio::runtime::Handle,
+    connections: &IDToConnection,
+) {
+    let clock = world.resource::<WorldClock>();
+    if !clock.every(IDLE_CHECK_INTERVAL) {
+        return;
+    }
+    let now = clock.elapsed_ms();
+
+    let mut timers = world.resource_mut::<IdleTimers>();
+    timers.0.retain(|id, _| connections.contains_key(id));
+
+    for conn in connections.iter() {
+        let id = *conn.key();
+        let timer = timers.0.entry(id).or_insert(IdleTimer {
+            last_input: now,
+            warned:     false,
+        });
+        let idle = now - timer.last_input;
+
+        if idle >= CONFIG.idle.timeout as u64 {
+            tracing::info!("player {} disconnected after {}s idle", id, idle / 1000);
+            conn.connection.close(VarInt::from_u32(2), b"idle");
+        } else if idle >= CONFIG.idle.warning as u64 && !timer.warned {
+            timer.warned = true;
+
+            let msg = crate::net::serialization::encode(
+                38,
+                IdleWarningData {
+                    remaining: (CONFIG.idle.timeout as u64 - idle) as u32,
+                },
+            )
+            .unwrap();
+            broadcast!(reliable_to, rt_handle, connections, id, msg);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use bevy_ecs::{resource::Resource, world::World};
+use shared::to_client::{LeaderboardData, LeaderboardEntryTO};
+
+use crate::{
+    config::config::LeaderboardScore,
+    structs::{
+        bevy::PlayerMap,
+        components::{Age, Name, Resources, Xp},
+    },
+    CONFIG,
+};
+
+/// The leaderboard last sent to the clients.
+#[derive(Resource, Default, Debug)]
+pub struct Leaderboard(pub Vec<LeaderboardEntryTO>);
+
+impl Leaderboard {
+    /// Returns the leaderboard as sent to the clients.
+    pub fn to_data(&self) -> LeaderboardData {
+        LeaderboardData {
+            entries: self.0.clone(),
+        }
+    }
+}
+
+/// Returns the XP earned since spawning. 'Xp' only holds the progress in the
+/// current age, the XP spent on the past ages is added back.
+pub fn total_xp(age: &Age, xp: &Xp) -> u32 {
+    let mut total = xp.0;
+    let mut needed = CONFIG.progression.first_age;
+    for _ in 1..age.0 {
+        total += needed;
+        needed = (needed as f32 * CONFIG.progression.xp_growth).round() as u32;
+    }
+
+    total
+}
+
+/// Returns the score of a player for the configured ranking.
+fn score(resources: &Resources, age: &Age, xp: &Xp) -> u32 {
+    match CONFIG.leaderboard.score {
+        LeaderboardScore::Gold => resources.3,
+        LeaderboardScore::Kills => resources.4,
+        LeaderboardScore::Xp => total_xp(age, xp),
+    }
+}
+
+/// Ranks the players. Ties go to the oldest player, the one with the lowest id.
+pub fn rank(world: &World) -> Vec<LeaderboardEntryTO> {
+    let mut entries: Vec<LeaderboardEntryTO> = world
+        .resource::<PlayerMap>()
+        .map
+        .iter()
+        .filter_map(|(&id, &entity)| {
+            let (name, resources, age, xp) = (
+                world.get::<Name>(entity)?,
+                world.get::<Resources>(entity)?,
+                world.get::<Age>(entity)?,
+                world.get::<Xp>(entity)?,
+            );
+
+            Some(LeaderboardEntryTO {
+                id,
+                name: name.0.clone(),
+                score: score(resources, age, xp),
+            })
+        })
+        .collect();
+
+    entries.sort_unstable_by(|a, b| b.score.cmp(&a.score).then(a.id.cmp(&b.id)));
+    entries.truncate(CONFIG.leaderboard.size as usize);
+    entries
+}
+
+/// Ranks the players and stores the result. Returns whether the leaderboard
+/// changed since the last update.
+pub fn update_leaderboard(world: &mut World) -> bool {
+    let entries = rank(world);
+
+    let mut leaderboard = world.resource_mut::<Leaderboard>();
+    if leaderboard.0 == entries {
+        return false;
+    }
+
+    leaderboard.0 = entries;
+    true
+}
@@ -0,0 +1,86 @@
+use nanorand::{Rng, WyRand};
+
+use crate::structs::components::AnimalType;
+
+/// What a drop gives to the killer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LootKind {
+    Food,
+    Gold,
+    Xp,
+}
+
+/// A single entry of a loot table.
+#[derive(Clone, Copy, Debug)]
+pub struct LootDrop {
+    pub kind:   LootKind,
+    // Probability of the drop, from 0 to 1.
+    pub chance: f32,
+    // Inclusive range of the amount dropped.
+    pub min:    u32,
+    pub max:    u32,
+}
+
+const WOLF_LOOT: &[LootDrop] = &[
+    LootDrop {
+        kind:   LootKind::Food,
+        chance: 1.,
+        min:    25,
+        max:    35,
+    },
+    LootDrop {
+        kind:   LootKind::Gold,
+        chance: 0.25,
+        min:    5,
+        max:    15,
+    },
+    LootDrop {
+        kind:   LootKind::Xp,
+        chance: 1.,
+        min:    40,
+        max:    60,
+    },
+];
+
+const FISH_LOOT: &[LootDrop] = &[
+    LootDrop {
+        kind:   LootKind::Food,
+        chance: 1.,
+        min:    8,
+        max:    12,
+    },
+    LootDrop {
+        kind:   LootKind::Xp,
+        chance: 1.,
+        min:    10,
+        max:    10,
+    },
+];
+
+/// Returns the loot table of the species.
+pub fn get_loot_table(animal: &AnimalType) -> &'static [LootDrop] {
+    match animal {
+        AnimalType::Wolf => WOLF_LOOT,
+        AnimalType::Fish => FISH_LOOT,
+    }
+}
+
+/// Rolls every drop of the table. Returns the food, gold and XP dropped.
+pub fn roll_loot(table: &[LootDrop], rng: &mut WyRand) -> (u32, u32, u32) {
+    let mut loot = (0, 0, 0);
+
+    for drop in table {
+        if rng.generate::<f32>() >= drop.chance {
+            continue;
+        }
+
+        let amount = rng.generate_range(drop.min..=drop.max);
+        match drop.kind {
+            LootKind::Food => loot.0 += amount,
+            LootKind::Gold => loot.1 += amount,
+            LootKind::Xp => loot.2 += amount,
+        }
+    }
+
+    loot
+}
@@ -0,0 +1,125 @@
+use bevy_ecs::{entity::Entity, query::With, world::World};
+use shared::{
+    objects::{GameObjects, StaticGameObjects},
+    to_client::{MinimapAllyTO, MinimapData, MinimapPointTO},
+};
+
+use crate::{
+    errors::ClientProducedError,
+    structs::{
+        bevy::PlayerMap,
+        clans::Clans,
+        clock::WorldClock,
+        components::{ClanId, LastPing, ObjectEntity, Position, SpawnPoint},
+    },
+    CONFIG,
+};
+
+/// Points of interest shown on the minimap.
+#[derive(Clone, Copy, Debug)]
+#[repr(u8)]
+pub enum MinimapPoint {
+    GoldMine = 0,
+    // The spawn point of the player, only they see it.
+    Base     = 1,
+}
+
+/// Returns the position scaled down to a byte per axis, the minimap doesn't
+/// need more.
+pub fn coarse(pos: &Position) -> (u8, u8) {
+    let scale = 255. / CONFIG.map.size as f32;
+    (
+        (pos.0 * scale).round().clamp(0., 255.) as u8,
+        (pos.1 * scale).round().clamp(0., 255.) as u8,
+    )
+}
+
+/// Returns the points of interest every player sees.
+pub fn minimap_points(world: &mut World) -> Vec<MinimapPointTO> {
+    let mut objects = world.query_filtered::<(&Position, &GameObjects), With<ObjectEntity>>();
+
+    objects
+        .iter(world)
+        .filter(|(_, object)| matches!(object, GameObjects::StaticGameObjects(StaticGameObjects::Gold)))
+        .map(|(pos, _)| {
+            let (x, y) = coarse(pos);
+            MinimapPointTO {
+                kind: MinimapPoint::GoldMine as u8,
+                x,
+                y,
+            }
+        })
+        .collect()
+}
+
+/// Returns the living clan members of the player, their own id excluded.
+pub fn clan_mates(world: &World, id: u32, entity: Entity) -> Vec<(u32, Entity)> {
+    let Some(clan) = world
+        .get::<ClanId>(entity)
+        .and_then(|c| world.resource::<Clans>().clans.get(&c.0))
+    else {
+        return Vec::new();
+    };
+
+    let player_map = world.resource::<PlayerMap>();
+    clan.members
+        .iter()
+        .filter(|&&member| member != id)
+        .filter_map(|member| player_map.map.get(member).map(|&e| (*member, e)))
+        .collect()
+}
+
+/// Returns the minimap of the player, their allies, their base and the points
+/// of interest.
+pub fn minimap_data(world: &World, id: u32, entity: Entity, points: &[MinimapPointTO]) -> MinimapData {
+    let allies = clan_mates(world, id, entity)
+        .into_iter()
+        .filter_map(|(ally, e)| {
+            let (x, y) = coarse(world.get::<Position>(e)?);
+            Some(MinimapAllyTO { id: ally, x, y })
+        })
+        .collect();
+
+    let mut points = points.to_vec();
+    if let Some(base) = world.get::<SpawnPoint>(entity) {
+        let (x, y) = coarse(&base.0);
+        points.push(MinimapPointTO {
+            kind: MinimapPoint::Base as u8,
+            x,
+            y,
+        });
+    }
+
+    MinimapData { allies, points }
+}
+
+/// Validates the map ping of the player against the map bounds and the ping
+/// cooldown. Returns the clan members to relay it to.
+pub fn accept_ping(world: &mut World, id: u32, x: f32, y: f32) -> Result<Vec<u32>, ClientProducedError> {
+    let size = CONFIG.map.size as f32;
+    if !(0. ..=size).contains(&x) || !(0. ..=size).contains(&y) {
+        return Err(ClientProducedError::InvalidPing("outside of the map"));
+    }
+
+    let entity = *world
+        .resource::<PlayerMap>()
+        .map
+        .get(&id)
+        .ok_or(ClientProducedError::InvalidPing("not a player"))?;
+
+    let mates = clan_mates(world, id, entity);
+    if mates.is_empty() {
+        return Err(ClientProducedError::InvalidPing("no clan members"));
+    }
+
+    let now = world.resource::<WorldClock>().elapsed_ms();
+    let mut last = world
+        .get_mut::<LastPing>(entity)
+        .ok_or(ClientProducedError::InvalidPing("not a player"))?;
+    if last.0.is_some_and(|t| now < t + CONFIG.minimap.ping_cooldown as u64) {
+        return Err(ClientProducedError::InvalidPing("too many pings"));
+    }
+    last.0 = Some(now);
+
+    Ok(mates.into_iter().map(|(mate, _)| mate).collect())
+}
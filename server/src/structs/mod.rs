@@ -1,5 +1,19 @@
+pub mod anticheat;
 pub mod bevy;
+pub mod chat;
+pub mod clans;
+pub mod clock;
+pub mod commands;
 pub mod components;
-pub mod quadtree;
+pub mod idle;
+pub mod leaderboard;
+pub mod loot;
+pub mod minimap;
+pub mod objects;
+pub mod rng;
 pub mod spatial_hash;
+pub mod spatial_index;
+pub mod spawns;
+pub mod store;
+pub mod upgrades;
 pub mod weapons;
@@ -0,0 +1,39 @@
+use shared::objects::{GameObjects, StaticGameObjects, UsableGameObjects};
+
+use crate::{structs::components::Resources, CONFIG};
+
+/// Returns the resources a single hit on the game object yields.
+/// Stored on the object as its 'Resources' component.
+pub fn get_object_yield(object: &GameObjects) -> Resources {
+    match object {
+        // wood, stone, food, gold, kills
+        GameObjects::StaticGameObjects(StaticGameObjects::Tree) => Resources(1, 0, 0, 0, 0),
+        GameObjects::StaticGameObjects(StaticGameObjects::Stone) => Resources(0, 1, 0, 0, 0),
+        GameObjects::StaticGameObjects(StaticGameObjects::Bush) => Resources(0, 0, 1, 0, 0),
+        GameObjects::StaticGameObjects(StaticGameObjects::Gold) => Resources(0, 0, 0, 1, 0),
+        GameObjects::StaticGameObjects(StaticGameObjects::Pine) => Resources(1, 0, 0, 0, 0),
+        GameObjects::StaticGameObjects(StaticGameObjects::Cactus) => Resources(0, 0, 1, 0, 0),
+        _ => Resources(0, 0, 0, 0, 0),
+    }
+}
+
+/// Returns the health restored by using the item.
+pub fn get_item_heal(item: &UsableGameObjects) -> f32 {
+    match item {
+        UsableGameObjects::Apple => CONFIG.items.apple_heal,
+    }
+}
+
+/// Returns the food spent by using the item.
+pub fn get_item_food_cost(item: &UsableGameObjects) -> u32 {
+    match item {
+        UsableGameObjects::Apple => CONFIG.items.apple_food_cost,
+    }
+}
+
+/// Returns the delay, in ms, before the item can be used again.
+pub fn get_item_cooldown(item: &UsableGameObjects) -> u32 {
+    match item {
+        UsableGameObjects::Apple => CONFIG.items.apple_cooldown,
+    }
+}
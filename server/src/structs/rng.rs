@@ -0,0 +1,58 @@
+use bevy_ecs::resource::Resource;
+use nanorand::{Rng, WyRand};
+
+/// The seed of the world. Every random stream is derived from it, so the same
+/// seed and the same inputs reproduce the same world.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct WorldSeed(pub u64);
+
+/// The independent random streams of the simulation.
+#[derive(Clone, Copy, Debug)]
+pub enum RngStream {
+    MapGen,
+    Ai,
+    Loot,
+    Spawn,
+    PlayerSpawn,
+}
+
+impl WorldSeed {
+    /// Initializes a seed from the system's entropy.
+    pub fn random() -> Self {
+        Self(WyRand::new().generate::<u64>())
+    }
+
+    /// Derives the generator of the stream. Streams never share state, so
+    /// consuming more numbers from one never shifts the others.
+    pub fn stream(&self, stream: RngStream) -> WyRand {
+        WyRand::new_seed(splitmix64(self.0 ^ splitmix64(stream as u64 + 1)))
+    }
+}
+
+/// Random stream used by map generation and the initial animal spawns.
+#[derive(Resource)]
+pub struct MapGenRng(pub WyRand);
+
+/// Random stream used by the animal AI.
+#[derive(Resource)]
+pub struct AiRng(pub WyRand);
+
+/// Random stream used for drops.
+#[derive(Resource)]
+pub struct LootRng(pub WyRand);
+
+/// Random stream used for respawning animals.
+#[derive(Resource)]
+pub struct SpawnRng(pub WyRand);
+
+/// Random stream used for spawning players.
+#[derive(Resource)]
+pub struct PlayerSpawnRng(pub WyRand);
+
+/// SplitMix64 finalizer, spreads the bits of similar seeds apart.
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
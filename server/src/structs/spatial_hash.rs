@@ -37,6 +37,11 @@ impl SpatialHash {
     }
 
     pub fn query(&self, x: f32, y: f32, radius: f32, found: &mut Vec<usize>) {
+        self.for_each(x, y, radius, |index| found.push(index));
+    }
+
+    /// Calls the function with every index in the cells the radius touches.
+    pub fn for_each(&self, x: f32, y: f32, radius: f32, mut f: impl FnMut(usize)) {
         let min_x = ((x - radius) / self.cell_size).floor() as i32;
         let max_x = ((x + radius) / self.cell_size).floor() as i32;
         let min_y = ((y - radius) / self.cell_size).floor() as i32;
@@ -46,7 +51,7 @@ impl SpatialHash {
             for gy in min_y..=max_y {
                 let key = ((gx as u64) << 32) | ((gy as u64) & 0xFFFFFFFF);
                 if let Some(entities) = self.grid.get(&key) {
-                    found.extend(entities);
+                    entities.iter().for_each(|&index| f(index));
                 }
             }
         }
@@ -0,0 +1,126 @@
+use bevy_ecs::{entity::Entity, resource::Resource};
+
+use crate::structs::{
+    components::{AnimalType, Position, Velocity},
+    spatial_hash::SpatialHash,
+};
+
+// Entities bigger than a cell (the walls) are kept out of the grid and returned
+// by every query.
+const CELL_SIZE: f32 = 256.;
+
+/// What an indexed entity is, so queries can filter without touching the
+/// world.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+    Player,
+    Animal(AnimalType),
+    Object,
+    Wall,
+}
+
+/// An entity registered in the spatial index, as it was when the index was
+/// built.
+#[derive(Clone, Copy, Debug)]
+pub struct IndexEntry {
+    pub entity:   Entity,
+    pub kind:     EntryKind,
+    pub pos:      Position,
+    pub vel:      Velocity,
+    pub radius:   f32,
+    pub reactive: bool,
+}
+
+/// Broad-phase spatial index of every entity with a position and a collider. It
+/// is rebuilt once per tick, and every system looking for nearby entities goes
+/// through it.
+///
+/// Queries push indices into 'entries'.
+#[derive(Resource)]
+pub struct SpatialIndex {
+    hash:        SpatialHash,
+    large:       Vec<usize>,
+    pub entries: Vec<IndexEntry>,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        Self {
+            hash:    SpatialHash::new(CELL_SIZE),
+            large:   Vec::new(),
+            entries: Vec::with_capacity(8192),
+        }
+    }
+}
+
+impl SpatialIndex {
+    /// Removes every entry, keeping the allocations for the next rebuild.
+    pub fn clear(&mut self) {
+        self.hash.clear();
+        self.large.clear();
+        self.entries.clear();
+    }
+
+    pub fn insert(&mut self, entry: IndexEntry) {
+        let index = self.entries.len();
+        if entry.radius > CELL_SIZE {
+            self.large.push(index);
+        } else {
+            self.hash.insert(entry.pos.0, entry.pos.1, index);
+        }
+        self.entries.push(entry);
+    }
+
+    /// Calls the function with every entry whose center may be within the
+    /// circle. This is the raw broad-phase, callers do the exact checks.
+    pub fn for_each(&self, x: f32, y: f32, radius: f32, mut f: impl FnMut(&IndexEntry)) {
+        self.hash.for_each(x, y, radius, |index| f(&self.entries[index]));
+        self.large.iter().for_each(|&index| f(&self.entries[index]));
+    }
+
+    /// Pushes the entries whose body overlaps the circle.
+    pub fn query_radius(&self, x: f32, y: f32, radius: f32, found: &mut Vec<usize>) {
+        self.for_each_index(x, y, radius, |index| {
+            let entry = &self.entries[index];
+            let reach = radius + entry.radius;
+            if (entry.pos.0 - x).powi(2) + (entry.pos.1 - y).powi(2) <= reach * reach {
+                found.push(index);
+            }
+        });
+    }
+
+    /// Pushes the entries whose body overlaps the rect, given by its center and
+    /// half extents.
+    pub fn query_rect(&self, x: f32, y: f32, half_w: f32, half_h: f32, found: &mut Vec<usize>) {
+        self.for_each_index(x, y, half_w.max(half_h), |index| {
+            let entry = &self.entries[index];
+            if (entry.pos.0 - x).abs() <= half_w + entry.radius && (entry.pos.1 - y).abs() <= half_h + entry.radius {
+                found.push(index);
+            }
+        });
+    }
+
+    /// Returns up to 'n' entries passing the filter, whose center is within the
+    /// radius, closest first.
+    pub fn nearest(&self, x: f32, y: f32, radius: f32, n: usize, filter: impl Fn(&IndexEntry) -> bool) -> Vec<usize> {
+        let mut found = Vec::new();
+        self.for_each_index(x, y, radius, |index| {
+            let entry = &self.entries[index];
+            let dist_sq = (entry.pos.0 - x).powi(2) + (entry.pos.1 - y).powi(2);
+            if dist_sq <= radius * radius && filter(entry) {
+                found.push((dist_sq, index));
+            }
+        });
+
+        found.sort_by(|a, b| a.0.total_cmp(&b.0));
+        found.into_iter().take(n).map(|(_, index)| index).collect()
+    }
+
+    /// Calls the function with every entry that may touch the circle.
+    fn for_each_index(&self, x: f32, y: f32, radius: f32, mut f: impl FnMut(usize)) {
+        // Entries are hashed by their center, extend the search so the ones
+        // sticking into the area are found too.
+        self.hash.for_each(x, y, radius + CELL_SIZE, &mut f);
+        self.large.iter().for_each(|&index| f(index));
+    }
+}
@@ -0,0 +1,340 @@
+use bevy_ecs::world::{Mut, World};
+use nanorand::Rng;
+
+use crate::{
+    errors::SpawnRejection,
+    structs::{
+        bevy::PlayerMap,
+        chat::censor,
+        clans::Clans,
+        components::{AnimalType, ClanId, Position},
+        rng::PlayerSpawnRng,
+        spatial_index::{EntryKind, SpatialIndex},
+    },
+    systems::{get_biome, Biome},
+    CONFIG,
+};
+
+// Biomes players spawn in, water and lava are not safe.
+const PLAYER_SPAWN_BIOMES: [Biome; 3] = [Biome::Snow, Biome::Grassland, Biome::Desert];
+
+// Random positions tried before the enemy check is dropped.
+const PLAYER_SPAWN_ATTEMPTS: u32 = 64;
+
+// Room kept free of colliders around a spawning player.
+const PLAYER_SPAWN_CLEARANCE: f32 = 60.;
+
+// Distance kept from the edges of the map, past the walls.
+const PLAYER_SPAWN_EDGE: f32 = 200.;
+
+/// A biome an animal species lives in.
+#[derive(Clone, Copy, Debug)]
+pub struct SpawnZone {
+    pub animal: AnimalType,
+    pub biome:  Biome,
+    // Share of the species population living in the zone.
+    pub share:  f32,
+}
+
+/// The spawn zones of every species. Animals remember the index of their zone,
+/// the shares of a species should add up to 1.
+pub const SPAWN_ZONES: &[SpawnZone] = &[
+    SpawnZone {
+        animal: AnimalType::Fish,
+        biome:  Biome::Ocean,
+        share:  1.,
+    },
+    SpawnZone {
+        animal: AnimalType::Wolf,
+        biome:  Biome::Grassland,
+        share:  0.5,
+    },
+    SpawnZone {
+        animal: AnimalType::Wolf,
+        biome:  Biome::Snow,
+        share:  0.3,
+    },
+    SpawnZone {
+        animal: AnimalType::Wolf,
+        biome:  Biome::Desert,
+        share:  0.2,
+    },
+];
+
+/// Returns the amount of animals the zone is kept at.
+pub fn get_zone_target(zone: &SpawnZone) -> u32 {
+    let species_target = match zone.animal {
+        AnimalType::Wolf => CONFIG.animals.max_wolf as f32,
+        AnimalType::Fish => CONFIG.animals.max_fish_alive as f32,
+    };
+
+    (species_target * zone.share).round() as u32
+}
+
+/// Returns the delay (ms) before a dead animal of the species is replaced.
+pub fn get_respawn_delay(animal: AnimalType) -> u32 {
+    match animal {
+        AnimalType::Wolf => CONFIG.animals.wolf_respawn_delay,
+        AnimalType::Fish => CONFIG.animals.fish_respawn_delay,
+    }
+}
+
+/// Picks a random land position for the player, clear of colliders and away
+/// from enemy players and wolves. With 'near_clan', the position is picked
+/// around a living clan member when there is one.
+pub fn find_player_spawn(world: &mut World, id: u32, near_clan: bool) -> (f32, f32) {
+    world.resource_scope(|world, mut rng: Mut<PlayerSpawnRng>| {
+        let clan = world.resource::<Clans>().clan_of(id);
+
+        // The living clan members to spawn around.
+        let anchors: Vec<Position> = match clan.filter(|_| near_clan) {
+            Some(clan) => {
+                let player_map = world.resource::<PlayerMap>();
+                world.resource::<Clans>().clans[&clan]
+                    .members
+                    .iter()
+                    .filter_map(|member| player_map.map.get(member))
+                    .filter_map(|&e| world.get::<Position>(e).copied())
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        // Enemies are checked first, once no position is found the player is
+        // at least kept out of colliders and lava.
+        for check_enemies in [true, false] {
+            for _ in 0..PLAYER_SPAWN_ATTEMPTS {
+                let (x, y) = match anchors.len() {
+                    0 => random_position(&mut rng),
+                    n => {
+                        let anchor = anchors[rng.0.generate_range(0..n)];
+                        let angle = rng.0.generate::<f32>() * std::f32::consts::TAU;
+                        let dist = rng.0.generate::<f32>() * CONFIG.spawn.clan_range;
+                        (anchor.0 + angle.cos() * dist, anchor.1 + angle.sin() * dist)
+                    }
+                };
+
+                if is_safe_spawn(world, x, y, clan, check_enemies) {
+                    return (x, y);
+                }
+            }
+        }
+
+        // Nothing found around the clan either, try anywhere.
+        for _ in 0..PLAYER_SPAWN_ATTEMPTS {
+            let (x, y) = random_position(&mut rng);
+            if is_safe_spawn(world, x, y, clan, false) {
+                return (x, y);
+            }
+        }
+
+        let center = CONFIG.map.size as f32 / 2.;
+        (center, center)
+    })
+}
+
+/// Returns a random position, far enough from the edges of the map.
+fn random_position(rng: &mut PlayerSpawnRng) -> (f32, f32) {
+    let range = CONFIG.map.size as f32 - 2. * PLAYER_SPAWN_EDGE;
+    (
+        PLAYER_SPAWN_EDGE + rng.0.generate::<f32>() * range,
+        PLAYER_SPAWN_EDGE + rng.0.generate::<f32>() * range,
+    )
+}
+
+/// Returns whether a player of the clan can spawn at the position.
+fn is_safe_spawn(world: &World, x: f32, y: f32, clan: Option<u32>, check_enemies: bool) -> bool {
+    let size = CONFIG.map.size as f32;
+    if !(PLAYER_SPAWN_EDGE..=size - PLAYER_SPAWN_EDGE).contains(&x)
+        || !(PLAYER_SPAWN_EDGE..=size - PLAYER_SPAWN_EDGE).contains(&y)
+    {
+        return false;
+    }
+    if !PLAYER_SPAWN_BIOMES.contains(&get_biome(x, y)) {
+        return false;
+    }
+
+    let index = world.resource::<SpatialIndex>();
+    let mut found = Vec::new();
+    index.query_radius(x, y, PLAYER_SPAWN_CLEARANCE, &mut found);
+    // The walls are only bounded by a circle, the edge check covers them.
+    if found.iter().any(|&i| index.entries[i].kind != EntryKind::Wall) {
+        return false;
+    }
+
+    if check_enemies {
+        let is_enemy = |entity| clan.is_none() || world.get::<ClanId>(entity).map(|c| c.0) != clan;
+        let enemies = index.nearest(x, y, CONFIG.spawn.enemy_range, 1, |entry| match entry.kind {
+            EntryKind::Player => is_enemy(entry.entity),
+            EntryKind::Animal(animal) => animal == AnimalType::Wolf,
+            _ => false,
+        });
+        if !enemies.is_empty() {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Returns whether the character takes no room, or moves the text around, when
+/// shown. Such characters let names look empty or like another player's.
+fn is_invisible(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            '\u{00AD}'
+                | '\u{034F}'
+                | '\u{180E}'
+                | '\u{200B}'..='\u{200F}'
+                | '\u{202A}'..='\u{202E}'
+                | '\u{2060}'..='\u{2064}'
+                | '\u{FEFF}'
+        )
+}
+
+/// Returns the name without its invisible characters, its whitespace collapsed.
+fn clean_name(name: &str) -> String {
+    // Tabs and newlines separate words like spaces.
+    let visible: String = name
+        .chars()
+        .filter(|&c| c.is_whitespace() || !is_invisible(c))
+        .collect();
+    visible.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Returns whether the name is reserved, case and symbols are ignored.
+fn is_reserved(name: &str) -> bool {
+    let normalize = |s: &str| -> String {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+
+    let name = normalize(name);
+    CONFIG.names.reserved.iter().any(|r| normalize(r) == name)
+}
+
+/// Validates the spawn request of the player. Returns the censored name to
+/// spawn them with.
+pub fn accept_spawn(world: &World, id: u32, name: &str) -> Result<String, SpawnRejection> {
+    // A living player would get a second entity.
+    if world.resource::<PlayerMap>().map.contains_key(&id) {
+        return Err(SpawnRejection::AlreadySpawned);
+    }
+
+    let name = clean_name(name);
+    if name.is_empty() {
+        return Ok(CONFIG.names.default.clone());
+    }
+    if name.chars().count() > CONFIG.names.max_length as usize {
+        return Err(SpawnRejection::NameTooLong);
+    }
+    if is_reserved(&name) {
+        return Err(SpawnRejection::ReservedName);
+    }
+
+    Ok(censor(&name))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::entity::Entity;
+
+    use super::*;
+
+    /// Returns a world with the player with the id alive.
+    fn world_with_player(id: u32) -> (World, Entity) {
+        let mut world = World::new();
+        world.insert_resource(PlayerMap::default());
+        let entity = world.spawn_empty().id();
+        world.resource_mut::<PlayerMap>().map.insert(id, entity);
+        (world, entity)
+    }
+
+    #[test]
+    fn rejects_living_players() {
+        let (world, _) = world_with_player(1);
+        assert!(matches!(
+            accept_spawn(&world, 1, "bob"),
+            Err(SpawnRejection::AlreadySpawned)
+        ));
+        assert_eq!(accept_spawn(&world, 2, "bob").unwrap(), "bob");
+    }
+
+    #[test]
+    fn dead_players_spawn_again() {
+        let (mut world, entity) = world_with_player(1);
+
+        // Dead players are removed from the player map.
+        world.resource_mut::<PlayerMap>().map.remove(&1);
+        world.despawn(entity);
+
+        assert_eq!(accept_spawn(&world, 1, "bob").unwrap(), "bob");
+    }
+
+    #[test]
+    fn strips_invisible_characters() {
+        let (world, _) = world_with_player(1);
+        assert_eq!(
+            accept_spawn(&world, 2, "\u{200B}b\u{202E}o\u{0007}b\u{FEFF}").unwrap(),
+            "bob"
+        );
+        assert_eq!(accept_spawn(&world, 2, "  big\t\nbob  ").unwrap(), "big bob");
+    }
+
+    #[test]
+    fn empty_names_get_the_default() {
+        let (world, _) = world_with_player(1);
+        assert_eq!(accept_spawn(&world, 2, "").unwrap(), CONFIG.names.default);
+        assert_eq!(
+            accept_spawn(&world, 2, " \u{200B}\u{2060} ").unwrap(),
+            CONFIG.names.default
+        );
+    }
+
+    #[test]
+    fn rejects_long_names() {
+        let (world, _) = world_with_player(1);
+        let max = CONFIG.names.max_length as usize;
+
+        assert!(accept_spawn(&world, 2, &"a".repeat(max)).is_ok());
+        assert!(matches!(
+            accept_spawn(&world, 2, &"a".repeat(max + 1)),
+            Err(SpawnRejection::NameTooLong)
+        ));
+        // Invisible characters don't count.
+        let padded = format!("{}\u{200B}\u{200B}", "a".repeat(max));
+        assert!(accept_spawn(&world, 2, &padded).is_ok());
+    }
+
+    #[test]
+    fn rejects_reserved_names() {
+        let (world, _) = world_with_player(1);
+        let reserved = &CONFIG.names.reserved[0];
+
+        assert!(matches!(
+            accept_spawn(&world, 2, reserved),
+            Err(SpawnRejection::ReservedName)
+        ));
+        assert!(matches!(
+            accept_spawn(&world, 2, &reserved.to_uppercase()),
+            Err(SpawnRejection::ReservedName)
+        ));
+        // Symbols and spaces don't hide them.
+        let spaced: String = reserved.chars().flat_map(|c| [c, '.']).collect();
+        assert!(matches!(
+            accept_spawn(&world, 2, &spaced),
+            Err(SpawnRejection::ReservedName)
+        ));
+        // Names only containing one are fine.
+        assert!(accept_spawn(&world, 2, &format!("{}x", reserved)).is_ok());
+    }
+
+    #[test]
+    fn censors_names() {
+        let (world, _) = world_with_player(1);
+        assert!(accept_spawn(&world, 2, "fuck").unwrap().contains('*'));
+    }
+}
@@ -0,0 +1,154 @@
+use crate::structs::components::Equipment;
+
+/// The slots a store item can be equipped in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum StoreKind {
+    Hat = 0,
+    Accessory = 1,
+}
+
+impl StoreKind {
+    pub fn from_u8(val: u8) -> Option<StoreKind> {
+        match val {
+            0 => Some(Self::Hat),
+            1 => Some(Self::Accessory),
+            _ => None,
+        }
+    }
+}
+
+/// Special effects of a store item, on top of its multipliers.
+#[derive(Clone, Copy, Debug)]
+pub enum StoreEffect {
+    None,
+    // Health per second, negative values drain.
+    Regen(f32),
+    // Fraction of the damage taken that is dealt back to the attacker.
+    Thorns(f32),
+}
+
+/// An entry of the store registry.
+#[derive(Clone, Copy, Debug)]
+pub struct StoreItem {
+    // Matches the client's 'hat_<id>.png' or 'access_<id>.png' asset.
+    pub id:        u8,
+    pub kind:      StoreKind,
+    // Gold.
+    pub price:     u32,
+    pub speed:     f32,
+    pub damage:    f32,
+    // Multiplies the damage taken, lower is better.
+    pub defense:   f32,
+    // Multiplies the knockback taken, lower is better.
+    pub knockback: f32,
+    pub effect:    StoreEffect,
+}
+
+impl StoreItem {
+    const fn new(id: u8, kind: StoreKind, price: u32) -> Self {
+        Self {
+            id,
+            kind,
+            price,
+            speed: 1.,
+            damage: 1.,
+            defense: 1.,
+            knockback: 1.,
+            effect: StoreEffect::None,
+        }
+    }
+}
+
+/// The store registry.
+pub const STORE: &[StoreItem] = &[
+    // Bull Helmet.
+    StoreItem {
+        damage: 1.5,
+        effect: StoreEffect::Regen(-5.),
+        ..StoreItem::new(7, StoreKind::Hat, 6000)
+    },
+    // Soldier Helmet.
+    StoreItem {
+        speed: 0.94,
+        defense: 0.75,
+        knockback: 0.5,
+        ..StoreItem::new(6, StoreKind::Hat, 4000)
+    },
+    // Booster Hat.
+    StoreItem {
+        speed: 1.16,
+        ..StoreItem::new(12, StoreKind::Hat, 4000)
+    },
+    // Medic Gear.
+    StoreItem {
+        effect: StoreEffect::Regen(3.),
+        ..StoreItem::new(13, StoreKind::Hat, 5000)
+    },
+    // Spike Gear.
+    StoreItem {
+        effect: StoreEffect::Thorns(0.45),
+        ..StoreItem::new(11, StoreKind::Hat, 10000)
+    },
+    // Snowball, purely cosmetic.
+    StoreItem::new(0, StoreKind::Accessory, 1000),
+    // Monkey Tail.
+    StoreItem {
+        speed: 1.35,
+        damage: 0.2,
+        ..StoreItem::new(11, StoreKind::Accessory, 2000)
+    },
+    // Apple Basket.
+    StoreItem {
+        effect: StoreEffect::Regen(1.),
+        ..StoreItem::new(17, StoreKind::Accessory, 3000)
+    },
+];
+
+/// Returns the store item of the kind with the id.
+pub fn get_store_item(kind: StoreKind, id: u8) -> Option<&'static StoreItem> {
+    STORE.iter().find(|i| i.kind == kind && i.id == id)
+}
+
+/// Returns the store items currently equipped.
+pub fn equipped_items(equipment: &Equipment) -> impl Iterator<Item = &'static StoreItem> {
+    let hat = equipment.0.and_then(|id| get_store_item(StoreKind::Hat, id));
+    let accessory = equipment.1.and_then(|id| get_store_item(StoreKind::Accessory, id));
+    hat.into_iter().chain(accessory)
+}
+
+pub fn get_speed_multiplier(equipment: &Equipment) -> f32 {
+    equipped_items(equipment).map(|i| i.speed).product()
+}
+
+pub fn get_damage_multiplier(equipment: &Equipment) -> f32 {
+    equipped_items(equipment).map(|i| i.damage).product()
+}
+
+pub fn get_defense_multiplier(equipment: &Equipment) -> f32 {
+    equipped_items(equipment).map(|i| i.defense).product()
+}
+
+pub fn get_knockback_multiplier(equipment: &Equipment) -> f32 {
+    equipped_items(equipment).map(|i| i.knockback).product()
+}
+
+/// Returns the health per second gained, or lost, from the equipment.
+pub fn get_regen(equipment: &Equipment) -> f32 {
+    equipped_items(equipment)
+        .map(|i| match i.effect {
+            StoreEffect::Regen(amount) => amount,
+            _ => 0.,
+        })
+        .sum()
+}
+
+/// Returns the fraction of the damage taken that is dealt back to the attacker.
+pub fn get_thorns(equipment: &Equipment) -> f32 {
+    equipped_items(equipment)
+        .map(|i| match i.effect {
+            StoreEffect::Thorns(fraction) => fraction,
+            _ => 0.,
+        })
+        .sum()
+}
@@ -0,0 +1,99 @@
+use serde::Deserialize;
+
+use crate::{structs::weapons::Weapon, CONFIG};
+
+/// What choosing an upgrade grants.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpgradeEffect {
+    Weapon(Weapon),
+    MaxHealth(f32),
+}
+
+/// An entry of the upgrade tree, read from the config. Ids are sent to the
+/// client.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Upgrade {
+    pub id:       u8,
+    // The age the upgrade unlocks at. One upgrade can be chosen per age.
+    pub age:      u8,
+    // The upgrade that must have been chosen before this one.
+    pub requires: Option<u8>,
+    pub effect:   UpgradeEffect,
+}
+
+/// Returns the upgrade with the id.
+pub fn get_upgrade(id: u8) -> Option<&'static Upgrade> {
+    CONFIG.progression.upgrades.iter().find(|u| u.id == id)
+}
+
+/// Returns the ids of the upgrades that can currently be chosen: those of the
+/// lowest unlocked age without a chosen upgrade, whose requirement is met.
+pub fn available_upgrades(age: u8, chosen: &[u8]) -> Vec<u8> {
+    let is_available = |u: &&Upgrade| {
+        u.age <= age
+            && !chosen.contains(&u.id)
+            && u.requires.is_none_or(|r| chosen.contains(&r))
+            && !chosen.iter().any(|c| get_upgrade(*c).is_some_and(|c| c.age == u.age))
+    };
+
+    let upgrades = &CONFIG.progression.upgrades;
+    let Some(tier) = upgrades.iter().filter(is_available).map(|u| u.age).min() else {
+        return Vec::new();
+    };
+
+    upgrades
+        .iter()
+        .filter(is_available)
+        .filter(|u| u.age == tier)
+        .map(|u| u.id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the age the upgrade unlocks at.
+    fn age_of(id: u8) -> u8 {
+        get_upgrade(id).unwrap().age
+    }
+
+    #[test]
+    fn nothing_before_age_two() {
+        assert!(available_upgrades(1, &[]).is_empty());
+    }
+
+    #[test]
+    fn lowest_age_comes_first() {
+        let lowest = CONFIG.progression.upgrades.iter().map(|u| u.age).min().unwrap();
+        let available = available_upgrades(CONFIG.progression.max_age, &[]);
+
+        assert!(!available.is_empty());
+        assert!(available.iter().all(|&id| age_of(id) == lowest));
+    }
+
+    #[test]
+    fn one_upgrade_per_age() {
+        let first = available_upgrades(CONFIG.progression.max_age, &[])[0];
+        let next = available_upgrades(CONFIG.progression.max_age, &[first]);
+
+        assert!(!next.contains(&first));
+        assert!(next.iter().all(|&id| age_of(id) > age_of(first)));
+    }
+
+    #[test]
+    fn requirements_must_be_chosen() {
+        for upgrade in CONFIG.progression.upgrades.iter().filter(|u| u.requires.is_some()) {
+            // Every other upgrade of the earlier ages chosen.
+            let chosen: Vec<u8> = CONFIG
+                .progression
+                .upgrades
+                .iter()
+                .filter(|u| u.age < upgrade.age && Some(u.id) != upgrade.requires)
+                .map(|u| u.id)
+                .collect();
+            assert!(!available_upgrades(upgrade.age, &chosen).contains(&upgrade.id));
+        }
+    }
+}
@@ -1,24 +1,62 @@
 use bevy_ecs::component::Component;
+use serde::Deserialize;
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[repr(u8)]
 pub enum Weapon {
-    Fists,
+    Fists = 0,
+    Sword = 1,
+    Daggers = 2,
 }
 
 pub fn get_weapon_range(weapon: &Weapon) -> f32 {
     match weapon {
         Weapon::Fists => 35.,
+        Weapon::Sword => 80.,
+        Weapon::Daggers => 45.,
     }
 }
 
 pub fn get_weapon_damage(weapon: &Weapon) -> f32 {
     match weapon {
         Weapon::Fists => 15.,
+        Weapon::Sword => 35.,
+        Weapon::Daggers => 20.,
     }
 }
 
 pub fn get_weapon_attack_speed(weapon: &Weapon) -> f32 {
     match weapon {
         Weapon::Fists => 300.,
+        Weapon::Sword => 400.,
+        Weapon::Daggers => 150.,
+    }
+}
+
+// Multiplier of the resources a hit gathers, rounded per resource.
+pub fn get_weapon_gather(weapon: &Weapon) -> f32 {
+    match weapon {
+        Weapon::Fists => 1.,
+        Weapon::Sword => 2.,
+        Weapon::Daggers => 1.5,
+    }
+}
+
+// Speed given to the targets along the hit direction.
+pub fn get_weapon_knockback(weapon: &Weapon) -> f32 {
+    match weapon {
+        Weapon::Fists => 20.,
+        Weapon::Sword => 45.,
+        Weapon::Daggers => 10.,
+    }
+}
+
+// Half angle of the swing, in radians.
+pub fn get_weapon_arc(weapon: &Weapon) -> f32 {
+    match weapon {
+        Weapon::Fists => 1.0,
+        Weapon::Sword => 1.2,
+        Weapon::Daggers => 0.8,
     }
 }
@@ -1,23 +1,25 @@
 use bevy_ecs::{
-    query::With,
-    resource::Resource,
-    system::{Query, ResMut},
-    world::World,
+    entity::Entity,
+    query::{With, Without},
+    system::{Commands, Query, Res, ResMut},
 };
 use nanorand::{Rng, WyRand};
 
 use crate::{
     structs::{
-        components::{AiState, AiTarget, AnimalEntity, AnimalType, Position, Velocity},
-        quadtree::{Point, Quadtree, Rect},
+        components::{
+            AiState, AiTarget, AnimalEntity, AnimalType, DamageCause, Equipment, Health, HealthUpdates, LastDamage,
+            LastHitBy, PlayerEntity, Position, ReloadState, ResourceUpdates, Resources, SpawnProtection, Velocity, Xp,
+        },
+        clock::{WorldClock, TICK_MS},
+        loot::{get_loot_table, roll_loot},
+        rng::{AiRng, LootRng},
+        spatial_index::{EntryKind, SpatialIndex},
+        store::get_defense_multiplier,
     },
-    systems::{Collider, NonReactiveCollider},
     CONFIG,
 };
 
-#[derive(Resource)]
-pub struct GlobalRng(pub WyRand);
-
 // TODO: move to config file.
 const VISUAL_RANGE: f32 = 200.0; // sqrt(40000)
 const VISUAL_RANGE_SQ: f32 = 40000.0;
@@ -26,204 +28,540 @@ const SEPARATION_FACTOR: f32 = 0.8;
 const ALIGNMENT_FACTOR: f32 = 0.08;
 const COHESION_FACTOR: f32 = 0.008;
 const WANDER_STRENGTH: f32 = 0.05;
+const WOLF_WANDER_SPEED: f32 = 8.0;
+const WOLF_CHASE_SPEED: f32 = 18.0;
+const WOLF_FLEE_SPEED: f32 = 22.0;
+// Distance between the centers of a wolf and a player for a bite to land.
+const WOLF_BITE_RANGE: f32 = 80.0;
+// Chased players are given up once they are this many times the aggro range
+// away.
+const WOLF_LEASH_FACTOR: f32 = 2.0;
+const FISH_FLEE_SPEED: f32 = 60.0;
+// Hit fish flee until they are this far from where they were hit from.
+const FISH_FLEE_RANGE: f32 = 600.0;
+// Share of the gap to the wanted velocity closed each tick. Knockback from hits
+// fades over a few ticks instead of being steered away at once.
+const STEER_RATE: f32 = 0.3;
+
+type AnimalData<'a> = (
+    Entity,
+    &'a mut Velocity,
+    &'a mut Position,
+    &'a mut AiState,
+    &'a mut AiTarget,
+    &'a AnimalType,
+    Option<(&'a Health, &'a mut ReloadState)>,
+);
+type PlayerPrey<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Position,
+        &'static mut Health,
+        &'static Equipment,
+        &'static mut LastDamage,
+        &'static SpawnProtection,
+    ),
+    (With<PlayerEntity>, Without<AnimalEntity>),
+>;
 
 /// System that handles animal entity movement.
 pub fn animal_ai_system(
-    mut rng: ResMut<GlobalRng>,
-    mut query: Query<(&mut Velocity, &mut Position, &mut AiState, &mut AiTarget, &AnimalType), With<AnimalEntity>>,
+    mut rng: ResMut<AiRng>,
+    clock: Res<WorldClock>,
+    index: Res<SpatialIndex>,
+    mut query: Query<AnimalData, With<AnimalEntity>>,
+    mut players: PlayerPrey,
+    mut health_updates: ResMut<HealthUpdates>,
 ) {
-    let snapshots: Vec<(Velocity, Position, AnimalType)> = query.iter().map(|(v, p, _, _, t)| (*v, *p, *t)).collect();
-
-    // build quadtree once from snapshots
-    let boundary = Rect::new(0.0, 0.0, 16384.0, 16384.0);
-    let mut qtree = Quadtree::new(boundary, 6);
-    for (i, (_, pos, animal_type)) in snapshots.iter().enumerate() {
-        if matches!(animal_type, AnimalType::Fish) {
-            qtree.insert(Point {
-                x:     pos.0,
-                y:     pos.1,
-                index: i,
-            });
-        }
-    }
+    // Wolves see further at night.
+    let aggro_range = if clock.is_night() {
+        CONFIG.animals.wolf_aggro_range * CONFIG.animals.wolf_night_aggro
+    } else {
+        CONFIG.animals.wolf_aggro_range
+    };
+
+    // Wolves share the players they bite, they are handled one after another.
+    for (wolf_id, mut vel, mut pos, mut state, mut target, animal_type, wolf) in query.iter_mut() {
+        let (AnimalType::Wolf, Some((health, mut bite))) = (animal_type, wolf) else {
+            continue;
+        };
+
+        bite.0 = bite.0.saturating_sub(TICK_MS as u32);
+        let tracked = target
+            .0
+            .and_then(|entity| players.get(entity).ok())
+            .map(|(entity, other, ..)| (entity, *other));
+        *state = wolf_think(*state, &mut target, health, &pos, &index, tracked, aggro_range);
 
-    let mut nearby = Vec::with_capacity(64);
-    for (mut vel, mut pos, state, mut target, animal_type) in query.iter_mut() {
         match *state {
             AiState::Idle => {
                 vel.0 *= 0.8;
                 vel.1 *= 0.8;
             }
 
-            AiState::Wander => match animal_type {
-                AnimalType::Wolf => {
-                    let dx = target.1 - pos.0;
-                    let dy = target.2 - pos.1;
-                    let dist_sq = dx * dx + dy * dy;
-
-                    if dist_sq < 25.0 {
-                        target.1 = pos.0 + (rng.0.generate::<f32>() - 0.5) * 512.;
-                        target.2 = pos.1 + (rng.0.generate::<f32>() - 0.5) * 512.;
-                    }
+            AiState::Wander => {
+                let dx = target.1 - pos.0;
+                let dy = target.2 - pos.1;
+                let dist_sq = dx * dx + dy * dy;
 
-                    let dist = dist_sq.sqrt();
-                    if dist > 0. {
-                        vel.0 = (dx / dist) * 35.0;
-                        vel.1 = (dy / dist) * 35.0;
-                    }
+                if dist_sq < 25.0 {
+                    let (min_x, max_x, min_y, max_y) = animal_bounds(animal_type);
+                    target.1 = (pos.0 + (rng.0.generate::<f32>() - 0.5) * 512.).clamp(min_x, max_x);
+                    target.2 = (pos.1 + (rng.0.generate::<f32>() - 0.5) * 512.).clamp(min_y, max_y);
                 }
 
-                AnimalType::Fish => {
-                    let mut neighbor_count = 0;
-                    let mut close_dx = 0.0_f32;
-                    let mut close_dy = 0.0_f32;
-                    let mut avg_vel_x = 0.0_f32;
-                    let mut avg_vel_y = 0.0_f32;
-                    let mut center_x = 0.0_f32;
-                    let mut center_y = 0.0_f32;
-                    
-                    let search = Rect::new(pos.0, pos.1, VISUAL_RANGE, VISUAL_RANGE);
-
-                    nearby.clear();
-                    qtree.query(&search, &mut nearby);
-
-                    for point in &nearby {
-                        let (other_vel, other_pos, _) = &snapshots[point.index];
-
-                        let dx = pos.0 - other_pos.0;
-                        let dy = pos.1 - other_pos.1;
-                        let dist_sq = dx * dx + dy * dy;
-
-                        if dist_sq == 0.0 || dist_sq > VISUAL_RANGE_SQ {
-                            continue;
-                        }
+                steer(&mut vel, dx, dy, WOLF_WANDER_SPEED);
+            }
 
-                        neighbor_count += 1;
+            AiState::Chase => {
+                let dx = target.1 - pos.0;
+                let dy = target.2 - pos.1;
+                let dist = (dx * dx + dy * dy).sqrt();
 
-                        if dist_sq < PROTECTED_RANGE_SQ {
-                            let force = (PROTECTED_RANGE_SQ - dist_sq) / PROTECTED_RANGE_SQ;
-                            close_dx += dx * force;
-                            close_dy += dy * force;
-                        }
+                // Keep closing in, but don't push into the player.
+                if dist > WOLF_BITE_RANGE * 0.5 {
+                    steer(&mut vel, dx, dy, WOLF_CHASE_SPEED);
+                } else {
+                    vel.0 -= vel.0 * STEER_RATE;
+                    vel.1 -= vel.1 * STEER_RATE;
+                }
 
-                        avg_vel_x += other_vel.0;
-                        avg_vel_y += other_vel.1;
-                        center_x += other_pos.0;
-                        center_y += other_pos.1;
+                if let Some(player) = target.0 {
+                    if dist <= WOLF_BITE_RANGE && bite.0 == 0 {
+                        if let Ok((entity, _, mut health, equipment, mut last_damage, protection)) =
+                            players.get_mut(player)
+                        {
+                            // Freshly spawned players can't be hurt, the wolf still waits to bite again.
+                            if !protection.active(clock.elapsed_ms()) {
+                                let damage = CONFIG.animals.wolf_bite_damage * get_defense_multiplier(equipment);
+                                // The killing bite is the one the kill feed shows.
+                                if health.0 > 0. {
+                                    *last_damage = LastDamage(Some(wolf_id), DamageCause::Wolf);
+                                }
+                                health.0 = (health.0 - damage).max(0.);
+                                health_updates.0.push((entity, health.0));
+                            }
+                            bite.0 = bite.1;
+                        }
                     }
+                }
+            }
 
-                    if neighbor_count > 0 {
-                        vel.0 += close_dx * SEPARATION_FACTOR;
-                        vel.1 += close_dy * SEPARATION_FACTOR;
+            AiState::Flee => {
+                steer(&mut vel, pos.0 - target.1, pos.1 - target.2, WOLF_FLEE_SPEED);
+            }
 
-                        let n = neighbor_count as f32;
-                        vel.0 += (avg_vel_x / n - vel.0) * ALIGNMENT_FACTOR;
-                        vel.1 += (avg_vel_y / n - vel.1) * ALIGNMENT_FACTOR;
-                        vel.0 += (center_x / n - pos.0) * COHESION_FACTOR;
-                        vel.1 += (center_y / n - pos.1) * COHESION_FACTOR;
-                    }
+            _ => {}
+        }
 
-                    let wander_angle = (rng.0.generate::<f32>() - 0.5) * 0.5;
-                    let speed = (vel.0 * vel.0 + vel.1 * vel.1).sqrt();
-
-                    if speed > 0.1 {
-                        let cos_a = wander_angle.cos();
-                        let sin_a = wander_angle.sin();
-                        let new_vx = vel.0 * cos_a - vel.1 * sin_a;
-                        let new_vy = vel.0 * sin_a + vel.1 * cos_a;
-                        vel.0 = new_vx;
-                        vel.1 = new_vy;
-                        vel.0 += (vel.0 / speed) * WANDER_STRENGTH;
-                        vel.1 += (vel.1 / speed) * WANDER_STRENGTH;
-                    } else {
-                        vel.0 += rng.0.generate::<f32>() - 0.5;
-                        vel.1 += rng.0.generate::<f32>() - 0.5;
-                    }
+        integrate(&mut vel, &mut pos, animal_type);
+    }
+
+    // Fish only read the index, so they are steered in parallel. Every fish gets
+    // its own random stream, the result doesn't depend on the thread it ran on.
+    let tick_seed = rng.0.generate::<u64>();
+    query
+        .par_iter_mut()
+        .for_each(|(entity, mut vel, mut pos, mut state, target, animal_type, _)| {
+            if !matches!(animal_type, AnimalType::Fish) {
+                return;
+            }
+
+            match *state {
+                AiState::Idle => {
+                    vel.0 *= 0.8;
+                    vel.1 *= 0.8;
+                }
+                AiState::Wander => {
+                    let mut rng = WyRand::new_seed(tick_seed ^ entity.to_bits());
+                    steer_fish(&index, &mut vel, &pos, &mut rng);
+                }
+                AiState::Flee => {
+                    let dx = pos.0 - target.1;
+                    let dy = pos.1 - target.2;
 
-                    let speed_sq = vel.0 * vel.0 + vel.1 * vel.1;
-                    const MAX_SPD: f32 = 60.0;
-                    const MIN_SPD: f32 = 20.0;
-                    if speed_sq > MAX_SPD * MAX_SPD {
-                        let s = MAX_SPD / speed_sq.sqrt();
-                        vel.0 *= s;
-                        vel.1 *= s;
-                    } else if speed_sq < MIN_SPD * MIN_SPD && speed_sq > 0.0 {
-                        let s = MIN_SPD / speed_sq.sqrt();
-                        vel.0 *= s;
-                        vel.1 *= s;
+                    // Far enough from the attacker, go back to the school.
+                    if dx * dx + dy * dy > FISH_FLEE_RANGE * FISH_FLEE_RANGE {
+                        *state = AiState::Wander;
                     }
+                    steer(&mut vel, dx, dy, FISH_FLEE_SPEED);
                 }
-            },
-            _ => {}
+                _ => {}
+            }
+
+            integrate(&mut vel, &mut pos, animal_type);
+        });
+}
+
+/// Boids steering. Fish keep away from the closest fish, and align with and
+/// move towards the ones they can see.
+fn steer_fish(index: &SpatialIndex, vel: &mut Velocity, pos: &Position, rng: &mut WyRand) {
+    let mut neighbor_count = 0;
+    let mut close_dx = 0.0_f32;
+    let mut close_dy = 0.0_f32;
+    let mut avg_vel_x = 0.0_f32;
+    let mut avg_vel_y = 0.0_f32;
+    let mut center_x = 0.0_f32;
+    let mut center_y = 0.0_f32;
+
+    index.for_each(pos.0, pos.1, VISUAL_RANGE, |other| {
+        if other.kind != EntryKind::Animal(AnimalType::Fish) {
+            return;
+        }
+
+        let dx = pos.0 - other.pos.0;
+        let dy = pos.1 - other.pos.1;
+        let dist_sq = dx * dx + dy * dy;
+
+        if dist_sq == 0.0 || dist_sq > VISUAL_RANGE_SQ {
+            return;
+        }
+
+        neighbor_count += 1;
+
+        if dist_sq < PROTECTED_RANGE_SQ {
+            let force = (PROTECTED_RANGE_SQ - dist_sq) / PROTECTED_RANGE_SQ;
+            close_dx += dx * force;
+            close_dy += dy * force;
+        }
+
+        avg_vel_x += other.vel.0;
+        avg_vel_y += other.vel.1;
+        center_x += other.pos.0;
+        center_y += other.pos.1;
+    });
+
+    if neighbor_count > 0 {
+        vel.0 += close_dx * SEPARATION_FACTOR;
+        vel.1 += close_dy * SEPARATION_FACTOR;
+
+        let n = neighbor_count as f32;
+        vel.0 += (avg_vel_x / n - vel.0) * ALIGNMENT_FACTOR;
+        vel.1 += (avg_vel_y / n - vel.1) * ALIGNMENT_FACTOR;
+        vel.0 += (center_x / n - pos.0) * COHESION_FACTOR;
+        vel.1 += (center_y / n - pos.1) * COHESION_FACTOR;
+    }
+
+    let wander_angle = (rng.generate::<f32>() - 0.5) * 0.5;
+    let speed = (vel.0 * vel.0 + vel.1 * vel.1).sqrt();
+
+    if speed > 0.1 {
+        let cos_a = wander_angle.cos();
+        let sin_a = wander_angle.sin();
+        let new_vx = vel.0 * cos_a - vel.1 * sin_a;
+        let new_vy = vel.0 * sin_a + vel.1 * cos_a;
+        vel.0 = new_vx;
+        vel.1 = new_vy;
+        vel.0 += (vel.0 / speed) * WANDER_STRENGTH;
+        vel.1 += (vel.1 / speed) * WANDER_STRENGTH;
+    } else {
+        vel.0 += rng.generate::<f32>() - 0.5;
+        vel.1 += rng.generate::<f32>() - 0.5;
+    }
+
+    let speed_sq = vel.0 * vel.0 + vel.1 * vel.1;
+    const MAX_SPD: f32 = 60.0;
+    const MIN_SPD: f32 = 20.0;
+    if speed_sq > MAX_SPD * MAX_SPD {
+        let s = MAX_SPD / speed_sq.sqrt();
+        vel.0 *= s;
+        vel.1 *= s;
+    } else if speed_sq < MIN_SPD * MIN_SPD && speed_sq > 0.0 {
+        let s = MIN_SPD / speed_sq.sqrt();
+        vel.0 *= s;
+        vel.1 *= s;
+    }
+}
+
+/// Moves the animal, and bounces it back into its bounds.
+fn integrate(vel: &mut Velocity, pos: &mut Position, animal_type: &AnimalType) {
+    let (min_x, max_x, min_y, max_y) = animal_bounds(animal_type);
+
+    pos.0 += vel.0;
+    pos.1 += vel.1;
+
+    if pos.0 <= min_x {
+        pos.0 = min_x;
+        if vel.0 < 0.0 {
+            vel.0 *= -1.5;
+        }
+        vel.0 += 2.0;
+    } else if pos.0 >= max_x {
+        pos.0 = max_x;
+        if vel.0 > 0.0 {
+            vel.0 *= -1.5;
         }
+        vel.0 -= 2.0;
+    }
+
+    if pos.1 <= min_y {
+        pos.1 = min_y;
+        if vel.1 < 0.0 {
+            vel.1 *= -1.5;
+        }
+        vel.1 += 2.0;
+    } else if pos.1 >= max_y {
+        pos.1 = max_y;
+        if vel.1 > 0.0 {
+            vel.1 *= -1.5;
+        }
+        vel.1 -= 2.0;
+    }
+
+    pos.0 = pos.0.clamp(min_x, max_x);
+    pos.1 = pos.1.clamp(min_y, max_y);
+}
 
-        let min_x = CONFIG.map.ocean_start_x as f32;
-        let max_x = CONFIG.map.ocean_end_x as f32;
-        let min_y = 0.0;
-        let max_y = CONFIG.map.size as f32;
+/// Picks the state of a wolf from its health and the players around it, and
+/// keeps the position of the tracked player in the target. 'tracked' is the
+/// player the wolf is already after, if they are still around.
+fn wolf_think(
+    state: AiState,
+    target: &mut AiTarget,
+    health: &Health,
+    pos: &Position,
+    index: &SpatialIndex,
+    tracked: Option<(Entity, Position)>,
+    aggro_range: f32,
+) -> AiState {
+    let dist_sq = |other: &Position| (other.0 - pos.0).powi(2) + (other.1 - pos.1).powi(2);
 
-        pos.0 += vel.0;
-        pos.1 += vel.1;
+    let nearest = index
+        .nearest(pos.0, pos.1, aggro_range, 1, |entry| entry.kind == EntryKind::Player)
+        .first()
+        .map(|&i| (index.entries[i].entity, index.entries[i].pos));
 
-        if pos.0 <= min_x {
-            pos.0 = min_x;
-            if vel.0 < 0.0 {
-                vel.0 *= -1.5;
+    let mut track = |entity: Entity, other: Position| {
+        target.0 = Some(entity);
+        target.1 = other.0;
+        target.2 = other.1;
+    };
+
+    // Hurt wolves run from the closest player.
+    if health.0 < health.1 * CONFIG.animals.wolf_flee_health {
+        if let Some((entity, other)) = nearest {
+            track(entity, other);
+            return AiState::Flee;
+        }
+    } else {
+        // Keep chasing until the player outruns the leash.
+        let leash = aggro_range * WOLF_LEASH_FACTOR;
+        if let (AiState::Chase, Some((entity, other))) = (state, tracked) {
+            if dist_sq(&other) <= leash * leash {
+                track(entity, other);
+                return AiState::Chase;
             }
-            vel.0 += 2.0;
-        } else if pos.0 >= max_x {
-            pos.0 = max_x;
-            if vel.0 > 0.0 {
-                vel.0 *= -1.5;
+        }
+
+        if let Some((entity, other)) = nearest {
+            track(entity, other);
+            return AiState::Chase;
+        }
+    }
+
+    // Nobody around, wander off from where we are.
+    if target.0.take().is_some() {
+        target.1 = pos.0;
+        target.2 = pos.1;
+    }
+    AiState::Wander
+}
+
+/// Makes the animal react to being hit by the attacker. Wolves turn on the
+/// attacker, fish flee from where the hit came from. Hurt wolves still flee,
+/// the next think picks that up.
+pub fn provoke(
+    animal_type: &AnimalType,
+    state: &mut AiState,
+    target: &mut AiTarget,
+    attacker: Entity,
+    attacker_pos: &Position,
+) {
+    match animal_type {
+        AnimalType::Wolf => {
+            target.0 = Some(attacker);
+            *state = AiState::Chase;
+        }
+        AnimalType::Fish => *state = AiState::Flee,
+    }
+
+    target.1 = attacker_pos.0;
+    target.2 = attacker_pos.1;
+}
+
+/// Moves the velocity towards the direction at the given speed.
+fn steer(vel: &mut Velocity, dx: f32, dy: f32, speed: f32) {
+    let dist = (dx * dx + dy * dy).sqrt();
+    if dist > 0. {
+        vel.0 += ((dx / dist) * speed - vel.0) * STEER_RATE;
+        vel.1 += ((dy / dist) * speed - vel.1) * STEER_RATE;
+    }
+}
+
+/// Returns the area the animal is kept in, as min x, max x, min y, max y.
+/// Fish stay in the ocean, wolves on the land above the lava.
+pub fn animal_bounds(animal_type: &AnimalType) -> (f32, f32, f32, f32) {
+    match animal_type {
+        AnimalType::Fish => (
+            CONFIG.map.ocean_start_x as f32,
+            CONFIG.map.ocean_end_x as f32,
+            0.,
+            CONFIG.map.size as f32,
+        ),
+        AnimalType::Wolf => (0., CONFIG.map.ocean_start_x as f32, 0., CONFIG.map.lava_start as f32),
+    }
+}
+
+/// Despawns the killed animals, and rolls the loot of their species for
+/// whoever killed them.
+pub fn animal_death_system(
+    mut commands: Commands,
+    mut rng: ResMut<LootRng>,
+    animals: Query<(Entity, &Health, &AnimalType, &LastHitBy), With<AnimalEntity>>,
+    mut players: Query<(&mut Resources, &mut Xp), With<PlayerEntity>>,
+    mut resource_updates: ResMut<ResourceUpdates>,
+) {
+    for (entity, health, animal_type, last_hit_by) in animals.iter() {
+        if health.0 > 0. {
+            continue;
+        }
+
+        if let Some(killer) = last_hit_by.0 {
+            if let Ok((mut resources, mut xp)) = players.get_mut(killer) {
+                let (food, gold, gained_xp) = roll_loot(get_loot_table(animal_type), &mut rng.0);
+                resources.2 += food;
+                resources.3 += gold;
+                resource_updates.0.push(killer);
+
+                // The progression system picks up the XP change.
+                if gained_xp > 0 {
+                    xp.0 += gained_xp;
+                }
             }
-            vel.0 -= 2.0;
         }
 
-        if pos.1 <= min_y {
-            pos.1 = min_y;
-            if vel.1 < 0.0 {
-                vel.1 *= -1.5;
+        commands.entity(entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use std::time::Instant;
+
+    use bevy_ecs::{
+        schedule::{ExecutorKind, IntoScheduleConfigs, Schedule},
+        world::World,
+    };
+
+    use super::*;
+    use crate::{structs::components::spawn_animal, systems::spatial_index_system};
+
+    /// Per tick cost of the fish, index rebuild included. Run with
+    /// 'cargo test --release -- --ignored --nocapture boids'.
+    #[test]
+    #[ignore]
+    fn boids_tick_cost() {
+        for fish in [4096, 16384] {
+            let mut world = World::new();
+            world.insert_resource(AiRng(WyRand::new_seed(6767)));
+            world.insert_resource(HealthUpdates::default());
+            world.insert_resource(SpatialIndex::default());
+            world.insert_resource(WorldClock::default());
+
+            let mut rng = WyRand::new_seed(fish);
+            let (min_x, max_x, min_y, max_y) = animal_bounds(&AnimalType::Fish);
+            let mut commands = world.commands();
+            for _ in 0..fish {
+                let x = min_x + rng.generate::<f32>() * (max_x - min_x);
+                let y = min_y + rng.generate::<f32>() * (max_y - min_y);
+                spawn_animal(&mut commands, AnimalType::Fish, 0, x, y);
             }
-            vel.1 += 2.0;
-        } else if pos.1 >= max_y {
-            pos.1 = max_y;
-            if vel.1 > 0.0 {
-                vel.1 *= -1.5;
+            world.flush();
+
+            let mut schedule = Schedule::default();
+            schedule.set_executor_kind(ExecutorKind::MultiThreaded);
+            schedule.add_systems((spatial_index_system, animal_ai_system).chain());
+
+            // Let the schools form before measuring.
+            for _ in 0..50 {
+                schedule.run(&mut world);
             }
-            vel.1 -= 2.0;
-        }
 
-        pos.0 = pos.0.clamp(min_x, max_x);
-        pos.1 = pos.1.clamp(min_y, max_y);
+            let ticks = 200;
+            let start = Instant::now();
+            for _ in 0..ticks {
+                schedule.run(&mut world);
+            }
+            let per_tick = start.elapsed().as_secs_f64() * 1000. / ticks as f64;
+            println!("{fish} fish: {per_tick:.3} ms per tick");
+        }
     }
 }
 
-pub fn init_animals(world: &mut World, rng: &mut GlobalRng) {
-    let min_x = CONFIG.map.ocean_start_x as f32;
-    let max_x = CONFIG.map.ocean_end_x as f32;
-    let min_y = 0.0;
-    let max_y = CONFIG.map.size as f32;
-
-    for _ in 0..(2_i32.pow(12)) {
-        let x = min_x + (rng.0.generate::<f32>() * (max_x - min_x));
-        let y = min_y + (rng.0.generate::<f32>() * (max_y - min_y));
-
-        world.spawn((
-            Position(x, y),
-            Velocity(
-                (rng.0.generate::<f32>() - 0.5) * 50.0,
-                (rng.0.generate::<f32>() - 0.5) * 50.0,
-            ),
-            AiState::Wander,
-            AiTarget(
-                None,
-                x + (rng.0.generate::<f32>() - 0.5) * 100.0,
-                y + (rng.0.generate::<f32>() - 0.5) * 100.0,
-            ),
-            AnimalType::Fish,
-            AnimalEntity,
-            Collider::circle(35.0),
-            NonReactiveCollider,
-        ));
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        schedule::{IntoScheduleConfigs, Schedule},
+        world::World,
+    };
+
+    use super::*;
+    use crate::{
+        structs::{
+            components::spawn_animal,
+            weapons::{get_weapon_knockback, Weapon},
+        },
+        systems::spatial_index_system,
+    };
+
+    #[test]
+    fn hit_wolves_are_pushed_back() {
+        let mut world = World::new();
+        world.insert_resource(AiRng(WyRand::new_seed(6767)));
+        world.insert_resource(HealthUpdates::default());
+        world.insert_resource(SpatialIndex::default());
+        world.insert_resource(WorldClock::default());
+
+        let player = world
+            .spawn((
+                PlayerEntity,
+                Position(1200., 1000.),
+                Health(100., 100.),
+                Equipment::default(),
+                LastDamage::default(),
+                SpawnProtection(0),
+            ))
+            .id();
+        let wolf = spawn_animal(&mut world.commands(), AnimalType::Wolf, 0, 1000., 1000.);
+        world.flush();
+
+        // The player hit the wolf with a sword, it turns on them.
+        let (mut state, mut target) = (AiState::Idle, AiTarget(None, 0., 0.));
+        provoke(
+            &AnimalType::Wolf,
+            &mut state,
+            &mut target,
+            player,
+            &Position(1200., 1000.),
+        );
+        world
+            .entity_mut(wolf)
+            .insert((state, target, Velocity(-get_weapon_knockback(&Weapon::Sword), 0.)));
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems((spatial_index_system, animal_ai_system).chain());
+        let x = |world: &World| world.get::<Position>(wolf).unwrap().0;
+
+        schedule.run(&mut world);
+        assert!(x(&world) < 1000., "the knockback was steered away");
+
+        // It slows down, then chases the player again.
+        for _ in 0..20 {
+            schedule.run(&mut world);
+        }
+        assert!(matches!(world.get::<AiState>(wolf), Some(AiState::Chase)));
+        assert!(world.get::<Velocity>(wolf).unwrap().0 > 0.);
     }
 }
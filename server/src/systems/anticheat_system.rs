@@ -0,0 +1,47 @@
+use bevy_ecs::{
+    entity::Entity,
+    query::With,
+    system::{Query, ResMut},
+};
+
+use crate::{
+    structs::{
+        anticheat::Violation,
+        clock::TICK_MS,
+        components::{LastPosition, PlayerEntity, Position, Suspicion, Velocity, Violations},
+    },
+    CONFIG,
+};
+
+/// Anti-Cheat System fn.
+/// Checks the positions of the players once the tick moved them. Players found
+/// outside of the map, or further than they can move in a tick, are put back
+/// where they were.
+pub fn anticheat_system(
+    mut query: Query<(Entity, &mut Position, &mut Velocity, &mut LastPosition, &mut Suspicion), With<PlayerEntity>>,
+    mut violations: ResMut<Violations>,
+) {
+    let size = CONFIG.map.size as f32;
+    let forgiven = CONFIG.anticheat.decay * TICK_MS as f32 / 1000.;
+
+    for (entity, mut pos, mut vel, mut last, mut suspicion) in query.iter_mut() {
+        suspicion.0 = (suspicion.0 - forgiven).max(0.);
+
+        // NaN isn't contained in any range.
+        let violation = if !(0. ..=size).contains(&pos.0) || !(0. ..=size).contains(&pos.1) {
+            Some(Violation::ImpossiblePosition)
+        } else if (pos.0 - last.0 .0).hypot(pos.1 - last.0 .1) > CONFIG.anticheat.max_step {
+            Some(Violation::Teleport)
+        } else {
+            None
+        };
+
+        if let Some(violation) = violation {
+            violations.0.push((entity, violation));
+            *pos = last.0;
+            *vel = Velocity(0., 0.);
+        }
+
+        last.0 = *pos;
+    }
+}
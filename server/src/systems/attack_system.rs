@@ -1,114 +1,296 @@
-use std::f32;
-
 use bevy_ecs::{
     entity::Entity,
-    query::With,
-    system::{Query, ResMut},
+    query::{Has, With, Without},
+    system::{Query, Res, ResMut},
 };
-use shared::objects::{GameObjects, StaticGameObjects};
 
 use crate::{
     structs::{
+        clans::are_allies,
+        clock::{WorldClock, TICK_MS},
         components::{
-            AimDir, AttackState, Health, HitEvent, HitEvents, ObjectEntity, PlayerEntity, Position, ReloadState, Resources
+            AiState, AiTarget, AimDir, AnimalEntity, AnimalType, AttackState, ClanId, DamageCause, Equipment, Health,
+            HealthUpdates, HitEvent, HitEvents, LastDamage, LastHitBy, ObjectEntity, PlayerEntity, Position,
+            ReloadState, Resources, ShadowLimited, SpawnProtection, Velocity, Xp,
+        },
+        spatial_index::{EntryKind, SpatialIndex},
+        store::{get_damage_multiplier, get_defense_multiplier, get_knockback_multiplier, get_thorns},
+        weapons::{
+            get_weapon_arc, get_weapon_damage, get_weapon_gather, get_weapon_knockback, get_weapon_range, Weapon,
         },
-        weapons::{Weapon, get_weapon_damage, get_weapon_range},
     },
-    systems::Collider,
+    systems::{provoke, Collider},
+    CONFIG,
 };
 
+/// Game objects that can be gathered from, with their resource yield.
+type ObjectTargets<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static Position, &'static Collider, &'static Resources),
+    (With<ObjectEntity>, Without<PlayerEntity>),
+>;
+
+// Knocked back entities are slowed down to this speed. Entities move 'speed *
+// 0.45' per tick, this keeps them from skipping over the smallest colliders,
+// so the collision system can still push them out.
+const MAX_KNOCKBACK_SPEED: f32 = 110.;
+
+/// Players that can be hit.
+type PlayerTargets<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Position,
+        &'static Collider,
+        &'static mut Health,
+        &'static mut Velocity,
+        &'static Equipment,
+        Option<&'static ClanId>,
+        &'static mut LastDamage,
+        &'static mut SpawnProtection,
+    ),
+    With<PlayerEntity>,
+>;
+
+/// Animals that can be hit, with what they need to react to it.
+type AnimalTargets<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Position,
+        &'static Collider,
+        &'static AnimalType,
+        &'static mut Health,
+        &'static mut Velocity,
+        &'static mut LastHitBy,
+        &'static mut AiState,
+        &'static mut AiTarget,
+    ),
+    (With<AnimalEntity>, Without<PlayerEntity>),
+>;
+
+/// Players that attack, with what their hits depend on.
+type Attackers<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Position,
+        &'static Collider,
+        &'static Weapon,
+        &'static mut ReloadState,
+        &'static AttackState,
+        &'static AimDir,
+        &'static mut Resources,
+        &'static mut Xp,
+        &'static Equipment,
+        Option<&'static ClanId>,
+        Has<ShadowLimited>,
+    ),
+    With<PlayerEntity>,
+>;
+
 /// Attack System fn.
-/// Handles Player attack actions.
+/// Handles Player attack actions, against players, game objects and animals.
+#[allow(clippy::too_many_arguments)]
 pub fn attack_system(
-    mut attackers: Query<
-        (
-            Entity,
-            &Position,
-            &Collider,
-            &Weapon,
-            &mut ReloadState,
-            &AttackState,
-            &AimDir,
-            &mut Resources,
-        ),
-        With<PlayerEntity>,
-    >,
-    object_targets: Query<(Entity, &Position, &Collider, &GameObjects), With<ObjectEntity>>,
-    mut player_targets: Query<(Entity, &Position, &Collider, &mut Health), With<PlayerEntity>>,
+    mut attackers: Attackers,
+    object_targets: ObjectTargets,
+    mut animal_targets: AnimalTargets,
+    mut player_targets: PlayerTargets,
+    index: Res<SpatialIndex>,
+    clock: Res<WorldClock>,
     mut hit_events: ResMut<HitEvents>,
+    mut health_updates: ResMut<HealthUpdates>,
 ) {
     // Clear past hit events.
     hit_events.0.clear();
 
+    // A pre-allocated buffer for the spatial index queries.
+    let mut nearby = Vec::with_capacity(32);
+
     // Tuple containing:
     // attacker id, attacker position, attacker collider, attacker weapon, attacker
-    // reload state, attacker attack state, and attacker resources.
-    for (attacker_id, pos, collider, weapon, mut reload_state, attack_state, aim, mut resources) in attackers.iter_mut()
+    // reload state, attacker attack state, attacker resources, attacker XP,
+    // attacker equipment, attacker clan and whether the anti-cheat limits the
+    // attacker.
+    for (
+        attacker_id,
+        pos,
+        collider,
+        weapon,
+        mut reload_state,
+        attack_state,
+        aim,
+        mut resources,
+        mut xp,
+        equipment,
+        clan,
+        limited,
+    ) in attackers.iter_mut()
     {
         // If the attacker reload is greater than zero.
         // This represents how much time left until we can register their next hit
         // event.
         if reload_state.0 > 0 {
-            reload_state.0 = reload_state.0.saturating_sub(67);
+            reload_state.0 = reload_state.0.saturating_sub(TICK_MS as u32);
         }
         // If the attacker's reload state is less than or equal to zero, we can register their next hit. AND if their
         // attack state is true, which represents if they're attacking or not.
         else if attack_state.0 {
+            let now = clock.elapsed_ms();
+
+            // Attacking ends the spawn protection, protected players can't fight back
+            // without risk.
+            if let Ok((.., mut protection)) = player_targets.get_mut(attacker_id) {
+                protection.0 = 0;
+            }
+
             // Store objects hit by the attacker.
             let mut object_hits = Vec::new();
             // Store players hit by the attacker.
             // let mut players_hits = Vec::new();
+            // Damage dealt back to the attacker by the targets' equipment, and the last
+            // target that dealt it.
+            let mut reflected = 0_f32;
+            let mut reflected_by = None;
 
-            // Loop through all players, collecting their id, position, and collider.
-            for (target_id, target_pos, target_collider, mut target_health) in player_targets.iter_mut() {
-                // Get the distance between the attacker and the target.
-                let dist = get_distance(pos, target_pos);
-
-                // If the distance between the attacker and the target is less than the
-                // scales + the attacker's weapon range, we can proceed.
-                if dist < collider.rad + target_collider.rad + get_weapon_range(weapon) {
-                    // TODO: fix angle logic.
-                    // Get the angle, in radians, of the attacker's position and the game object's
-                    // position.
-                    let angle = (pos.1 - target_pos.1).atan2(pos.0 - target_pos.0);
-
-                    // If the angle difference is greather than PI.
-                    if angle_diff(aim.0, angle).abs() > f32::consts::PI / 2.0 {
-                        // This satisfies all criteria, add it to the Vec.
-                        // players_hits.push((target_id, angle));
-                        target_health.0 = target_health.0.clamp(0., target_health.0 - get_weapon_damage(weapon));
-                    }
-                }
+            // Find the players and game objects in reach through the spatial index.
+            // Limited attackers swing, but never hit anything.
+            nearby.clear();
+            if !limited {
+                index.query_radius(pos.0, pos.1, collider.rad + get_weapon_range(weapon), &mut nearby);
             }
 
-            // Loop through all game objects, collecting their id, position, collider, and
-            // their type.
-            for (target_id, target_pos, target_collider, game_obj) in object_targets.iter() {
-                // Get the distance between the attacker and the game object.
-                let dist = get_distance(pos, target_pos);
-
-                // If the distance between the attacker and the game object is less than the
-                // scales + the attacker's weapon range, we can proceed.
-                if dist < collider.rad + target_collider.rad + get_weapon_range(weapon) {
-                    // TODO: fix angle logic.
-                    // Get the angle, in radians, of the attacker's position and the game object's
-                    // position.
-                    let angle = (pos.1 - target_pos.1).atan2(pos.0 - target_pos.0);
-
-                    // If the angle difference is greather than PI.
-                    if angle_diff(aim.0, angle).abs() > f32::consts::PI / 2.0 {
-                        // This satisfies all criteria, add it to the Vec.
-                        object_hits.push((target_id, angle));
-
-                        // Handle resources.
-                        match game_obj {
-                            GameObjects::StaticGameObjects(StaticGameObjects::Tree) => {
-                                resources.0 += 1;
+            for entry in nearby.iter().map(|&i| &index.entries[i]) {
+                match entry.kind {
+                    EntryKind::Player => {
+                        // Players can't hit themselves.
+                        if entry.entity == attacker_id {
+                            continue;
+                        }
+
+                        // Get the target's position, collider, health and equipment.
+                        let Ok((
+                            target_id,
+                            target_pos,
+                            target_collider,
+                            mut target_health,
+                            mut target_vel,
+                            target_equipment,
+                            target_clan,
+                            mut last_damage,
+                            protection,
+                        )) = player_targets.get_mut(entry.entity)
+                        else {
+                            continue;
+                        };
+
+                        // Allies can't hurt each other, and freshly spawned players can't be hurt.
+                        if are_allies(clan, target_clan) || protection.active(now) {
+                            continue;
+                        }
+
+                        // The target must be caught in the swing.
+                        if in_swing(pos, collider.rad, aim.0, weapon, target_pos, target_collider.rad) {
+                            // The damage is scaled by both the attacker's and the target's
+                            // equipment.
+                            let damage = get_weapon_damage(weapon)
+                                * get_damage_multiplier(equipment)
+                                * get_defense_multiplier(target_equipment);
+                            let thorns = damage * get_thorns(target_equipment);
+                            if thorns > 0. {
+                                reflected += thorns;
+                                reflected_by = Some(target_id);
+                            }
+
+                            let was_alive = target_health.0 > 0.;
+                            if was_alive {
+                                *last_damage = LastDamage(Some(attacker_id), DamageCause::Weapon(*weapon));
+                            }
+                            target_health.0 = (target_health.0 - damage).max(0.);
+                            health_updates.0.push((target_id, target_health.0));
+
+                            let strength = get_weapon_knockback(weapon) * get_knockback_multiplier(target_equipment);
+                            knock_back(&mut target_vel, pos, target_pos, aim.0, strength);
+
+                            // The hit killed the target, award the kill.
+                            if was_alive && target_health.0 <= 0. {
+                                resources.4 += 1;
+                                xp.0 += CONFIG.progression.kill_xp;
                             }
-                            _ => {}
                         }
-                        // break; // one object per swing
                     }
+
+                    EntryKind::Object => {
+                        // Get the game object's position, collider, and resource yield.
+                        let Ok((target_id, target_pos, target_collider, yields)) = object_targets.get(entry.entity)
+                        else {
+                            continue;
+                        };
+
+                        // The game object must be caught in the swing.
+                        if in_swing(pos, collider.rad, aim.0, weapon, target_pos, target_collider.rad) {
+                            // The hit animation pushes the object away from the attacker, send the
+                            // direction from the object to the attacker.
+                            let angle = (pos.1 - target_pos.1).atan2(pos.0 - target_pos.0);
+                            object_hits.push((target_id, angle));
+
+                            // Handle resources, scaled by the weapon's gather multiplier.
+                            // Gathering also awards XP.
+                            let gathered = gather(&mut resources, yields, get_weapon_gather(weapon));
+                            xp.0 += gathered * CONFIG.progression.gather_xp;
+                        }
+                    }
+
+                    EntryKind::Animal(_) => {
+                        let Ok((
+                            target_pos,
+                            target_collider,
+                            animal_type,
+                            mut target_health,
+                            mut target_vel,
+                            mut last_hit_by,
+                            mut state,
+                            mut target,
+                        )) = animal_targets.get_mut(entry.entity)
+                        else {
+                            continue;
+                        };
+
+                        // Animals killed this tick are despawned after the attacks.
+                        if target_health.0 <= 0. {
+                            continue;
+                        }
+
+                        if in_swing(pos, collider.rad, aim.0, weapon, target_pos, target_collider.rad) {
+                            // Animals have no equipment, only the attacker's counts.
+                            let damage = get_weapon_damage(weapon) * get_damage_multiplier(equipment);
+                            target_health.0 = (target_health.0 - damage).max(0.);
+                            knock_back(&mut target_vel, pos, target_pos, aim.0, get_weapon_knockback(weapon));
+
+                            // The killer gets the loot.
+                            last_hit_by.0 = Some(attacker_id);
+                            provoke(animal_type, &mut state, &mut target, attacker_id, pos);
+                        }
+                    }
+
+                    _ => {}
+                }
+            }
+
+            // Apply the reflected damage to the attacker.
+            if reflected > 0. {
+                if let Ok((_, _, _, mut attacker_health, _, _, _, mut last_damage, _)) =
+                    player_targets.get_mut(attacker_id)
+                {
+                    if attacker_health.0 > 0. {
+                        *last_damage = LastDamage(reflected_by, DamageCause::Thorns);
+                    }
+                    attacker_health.0 = (attacker_health.0 - reflected).max(0.);
+                    health_updates.0.push((attacker_id, attacker_health.0));
                 }
             }
 
@@ -127,11 +309,71 @@ pub fn attack_system(
     }
 }
 
-/// Function that returns the Euclidean distance between 2 points.
-fn get_distance(a: &Position, b: &Position) -> f32 {
-    let dx = a.0 - b.0;
-    let dy = a.1 - b.1;
-    ((dx * dx) + (dy * dy)).sqrt()
+/// Function that adds the yield of a game object to the resources, scaled by
+/// the multiplier. Returns the total amount gathered.
+fn gather(resources: &mut Resources, yields: &Resources, multiplier: f32) -> u32 {
+    let scale = |amount: u32| (amount as f32 * multiplier).round() as u32;
+    let gathered = (scale(yields.0), scale(yields.1), scale(yields.2), scale(yields.3));
+
+    resources.0 += gathered.0;
+    resources.1 += gathered.1;
+    resources.2 += gathered.2;
+    resources.3 += gathered.3;
+
+    gathered.0 + gathered.1 + gathered.2 + gathered.3
+}
+
+/// Function that returns true if a target is caught in the swing of the
+/// weapon. The swing is a cone of the weapon's range and arc around the aim
+/// direction, the target is hit if any part of it is inside the cone.
+fn in_swing(pos: &Position, radius: f32, aim: f32, weapon: &Weapon, target_pos: &Position, target_radius: f32) -> bool {
+    // Vector from the attacker to the target.
+    let dx = target_pos.0 - pos.0;
+    let dy = target_pos.1 - pos.1;
+    let dist = (dx * dx + dy * dy).sqrt();
+
+    if dist >= radius + target_radius + get_weapon_range(weapon) {
+        return false;
+    }
+
+    // The attacker is inside the target, there is no direction to miss in.
+    if dist <= target_radius {
+        return true;
+    }
+
+    // The target covers an angle on top of the weapon's arc.
+    let angle = dy.atan2(dx);
+    let spread = (target_radius / dist).asin();
+
+    angle_diff(aim, angle).abs() <= get_weapon_arc(weapon) + spread
+}
+
+/// Function that pushes the target away from the attacker, adding the
+/// knockback to its velocity. The movement and collision systems move it on the
+/// next tick, like any other movement.
+fn knock_back(vel: &mut Velocity, pos: &Position, target_pos: &Position, aim: f32, strength: f32) {
+    if strength <= 0. {
+        return;
+    }
+
+    // Push along the hit direction, or the aim if they are on top of each other.
+    let dx = target_pos.0 - pos.0;
+    let dy = target_pos.1 - pos.1;
+    let dist = (dx * dx + dy * dy).sqrt();
+    let (dir_x, dir_y) = if dist > 0. {
+        (dx / dist, dy / dist)
+    } else {
+        (aim.cos(), aim.sin())
+    };
+
+    vel.0 += dir_x * strength;
+    vel.1 += dir_y * strength;
+
+    let speed = (vel.0 * vel.0 + vel.1 * vel.1).sqrt();
+    if speed > MAX_KNOCKBACK_SPEED {
+        vel.0 *= MAX_KNOCKBACK_SPEED / speed;
+        vel.1 *= MAX_KNOCKBACK_SPEED / speed;
+    }
 }
 
 /// Function that returns the angle difference between 2 angles.
@@ -143,3 +385,127 @@ fn angle_diff(a: f32, b: f32) -> f32 {
         diff
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use super::*;
+
+    const RADIUS: f32 = 35.;
+
+    /// Swings the weapon from the origin at a point target placed at the angle
+    /// and distance.
+    fn swing_at(weapon: Weapon, aim: f32, angle: f32, dist: f32) -> bool {
+        let target = Position(angle.cos() * dist, angle.sin() * dist);
+        in_swing(&Position(0., 0.), RADIUS, aim, &weapon, &target, 0.)
+    }
+
+    #[test]
+    fn gathers_more_with_better_weapons() {
+        let yields = Resources(1, 2, 0, 0, 0);
+        let gathered = |weapon: Weapon| {
+            let mut resources = Resources(0, 0, 0, 0, 0);
+            gather(&mut resources, &yields, get_weapon_gather(&weapon));
+            (resources.0, resources.1)
+        };
+
+        assert_eq!(gathered(Weapon::Fists), (1, 2));
+        assert_eq!(gathered(Weapon::Daggers), (2, 3));
+        assert_eq!(gathered(Weapon::Sword), (2, 4));
+    }
+
+    #[test]
+    fn hits_in_front() {
+        let target = Position(60., 0.);
+        assert!(in_swing(&Position(0., 0.), RADIUS, 0., &Weapon::Fists, &target, RADIUS));
+    }
+
+    #[test]
+    fn misses_behind() {
+        let target = Position(-60., 0.);
+        assert!(!in_swing(
+            &Position(0., 0.),
+            RADIUS,
+            0.,
+            &Weapon::Fists,
+            &target,
+            RADIUS
+        ));
+    }
+
+    #[test]
+    fn misses_out_of_range() {
+        let reach = RADIUS + get_weapon_range(&Weapon::Sword);
+        assert!(swing_at(Weapon::Sword, 0., 0., reach - 1.));
+        assert!(!swing_at(Weapon::Sword, 0., 0., reach + 1.));
+    }
+
+    #[test]
+    fn angle_points_from_attacker_to_target() {
+        // Aiming down (positive y) hits below the attacker, not above.
+        assert!(swing_at(Weapon::Fists, FRAC_PI_2, FRAC_PI_2, 50.));
+        assert!(!swing_at(Weapon::Fists, FRAC_PI_2, -FRAC_PI_2, 50.));
+    }
+
+    #[test]
+    fn arc_edges() {
+        for weapon in [Weapon::Fists, Weapon::Sword, Weapon::Daggers] {
+            let arc = get_weapon_arc(&weapon);
+            assert!(swing_at(weapon, 0., arc - 0.01, 50.));
+            assert!(swing_at(weapon, 0., -(arc - 0.01), 50.));
+            assert!(!swing_at(weapon, 0., arc + 0.01, 50.));
+            assert!(!swing_at(weapon, 0., -(arc + 0.01), 50.));
+        }
+    }
+
+    #[test]
+    fn arc_wraps_around() {
+        // Aim and target on both sides of the -PI/PI seam.
+        assert!(swing_at(Weapon::Fists, PI - 0.1, -PI + 0.1, 50.));
+        assert!(swing_at(Weapon::Fists, -PI + 0.1, PI - 0.1, 50.));
+    }
+
+    #[test]
+    fn big_targets_widen_the_arc() {
+        // Just outside the arc, but the object is big enough to stick into it.
+        let arc = get_weapon_arc(&Weapon::Fists);
+        let angle = arc + 0.2;
+        let target = Position(angle.cos() * 100., angle.sin() * 100.);
+        assert!(in_swing(&Position(0., 0.), RADIUS, 0., &Weapon::Fists, &target, 60.));
+        assert!(!in_swing(&Position(0., 0.), RADIUS, 0., &Weapon::Fists, &target, 5.));
+    }
+
+    #[test]
+    fn overlapping_target_is_hit() {
+        assert!(in_swing(
+            &Position(0., 0.),
+            RADIUS,
+            0.,
+            &Weapon::Fists,
+            &Position(-10., 0.),
+            60.
+        ));
+    }
+
+    #[test]
+    fn arc_depends_on_the_weapon() {
+        let angle = (get_weapon_arc(&Weapon::Daggers) + get_weapon_arc(&Weapon::Sword)) / 2.;
+        assert!(swing_at(Weapon::Sword, 0., angle, 50.));
+        assert!(!swing_at(Weapon::Daggers, 0., angle, 50.));
+    }
+
+    #[test]
+    fn knockback_pushes_away_from_the_attacker() {
+        let mut vel = Velocity(0., 0.);
+        knock_back(&mut vel, &Position(0., 0.), &Position(0., 50.), 0., 20.);
+        assert!(vel.0.abs() < 1e-4 && (vel.1 - 20.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn knockback_is_capped() {
+        let mut vel = Velocity(100., 0.);
+        knock_back(&mut vel, &Position(0., 0.), &Position(50., 0.), 0., 45.);
+        assert!((vel.0 - MAX_KNOCKBACK_SPEED).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,53 @@
+use bevy_ecs::{
+    entity::Entity,
+    query::With,
+    system::{Query, Res, ResMut},
+};
+
+use crate::{
+    structs::{
+        clock::WorldClock,
+        components::{DamageCause, Health, HealthUpdates, LastDamage, PlayerEntity, Position, SpawnProtection},
+    },
+    systems::{get_biome, Biome},
+};
+
+// ms between two steps of damage over time.
+const DAMAGE_INTERVAL: u32 = 1000;
+
+/// Biome System fn.
+/// Applies the damage over time, or healing, of the biome each player is in.
+pub fn biome_system(
+    mut query: Query<(Entity, &Position, &mut Health, &mut LastDamage, &SpawnProtection), With<PlayerEntity>>,
+    clock: Res<WorldClock>,
+    mut health_updates: ResMut<HealthUpdates>,
+) {
+    if !clock.every(DAMAGE_INTERVAL) {
+        return;
+    }
+
+    let now = clock.elapsed_ms();
+    for (entity, pos, mut health, mut last_damage, protection) in query.iter_mut() {
+        let biome = get_biome(pos.0, pos.1);
+        let damage = biome.effects().damage;
+
+        // Dead players are left alone, freshly spawned ones still heal.
+        if damage == 0. || health.0 <= 0. || (damage > 0. && protection.active(now)) {
+            continue;
+        }
+
+        if damage > 0. {
+            let cause = match biome {
+                Biome::Lava => DamageCause::Lava,
+                _ => DamageCause::Biome,
+            };
+            *last_damage = LastDamage(None, cause);
+        }
+
+        let new_health = (health.0 - damage).clamp(0., health.1);
+        if new_health != health.0 {
+            health.0 = new_health;
+            health_updates.0.push((entity, new_health));
+        }
+    }
+}
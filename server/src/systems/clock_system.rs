@@ -0,0 +1,9 @@
+use bevy_ecs::system::ResMut;
+
+use crate::structs::clock::WorldClock;
+
+/// Clock System fn.
+/// Advances the world clock, runs before every other system.
+pub fn clock_system(mut clock: ResMut<WorldClock>) {
+    clock.ticks += 1;
+}
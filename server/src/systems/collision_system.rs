@@ -1,8 +1,10 @@
+use std::collections::HashMap;
+
 use bevy_ecs::{
     component::Component,
     entity::Entity,
     query::{With, Without},
-    system::Query,
+    system::{Query, Res},
 };
 use rapier2d::{
     math::{Isometry, Vector},
@@ -13,8 +15,9 @@ use rapier2d::{
     prelude::SharedShape,
 };
 
-use crate::structs::components::Position;
-use crate::structs::quadtree::{Point, Quadtree, Rect};
+use crate::structs::clans::are_allies;
+use crate::structs::components::{ClanId, Owner, Position};
+use crate::structs::spatial_index::SpatialIndex;
 
 /// The collider struct that is contained in ECS bundles.
 #[derive(Component, Clone)]
@@ -53,12 +56,21 @@ pub struct NonReactiveCollider;
 #[derive(Component)]
 pub struct ReactiveCollider;
 
-// TODO: Implement a Spatial Hash.
+/// Colliders that don't move, walls, structures and game objects.
+type NonReactiveColliders<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Position, &'static Collider, Option<&'static Owner>),
+    (With<NonReactiveCollider>, Without<ReactiveCollider>),
+>;
+
 /// Colliion system fn.
 /// Handles collision between entities.
 pub fn collision_resolution_system(
-    mut reactive_query: Query<(Entity, &mut Position, &Collider), With<ReactiveCollider>>,
-    non_reactive_query: Query<(&Position, &Collider), (With<NonReactiveCollider>, Without<ReactiveCollider>)>,
+    index: Res<SpatialIndex>,
+    mut reactive_query: Query<(Entity, &mut Position, &Collider, Option<&ClanId>), With<ReactiveCollider>>,
+    non_reactive_query: NonReactiveColliders,
+    clans: Query<&ClanId>,
 ) {
     // The level of precision to use.
     let precision = 0.1;
@@ -67,7 +79,7 @@ pub fn collision_resolution_system(
     let mut reactives: Vec<_> = reactive_query
         .iter_mut()
         .enumerate()
-        .map(|(idx, (entity, pos, col))| (idx, entity, pos, col))
+        .map(|(idx, (entity, pos, col, _))| (idx, entity, pos, col))
         .collect();
 
     // If there are no reactive colliders, exit. There would be no point because
@@ -76,52 +88,37 @@ pub fn collision_resolution_system(
         return;
     }
 
-    // Prepare a quadtree.
-    // TODO: Move the quadtree into a bevy ecs Resource and rebuild it instead.
-    let boundary = Rect::new(8192.0, 8192.0, 8192.0, 8192.0);
-    let mut qtree = Quadtree::new(boundary, 6);
-
-    // Insert all reactive collider entities into the quadtree.
-    for (idx, _, pos, _) in &reactives {
-        qtree.insert(Point {
-            x:     pos.0,
-            y:     pos.1,
-            index: *idx,
-        });
-    }
+    // Maps the reactive entities to their index in the Vec, the spatial index only
+    // knows about entities.
+    let slots: HashMap<Entity, usize> = reactives.iter().map(|(idx, entity, _, _)| (*entity, *idx)).collect();
 
-    // A pre-allocated buffer to pass into 'Quadtree::query' to contain the
+    // A pre-allocated buffer to pass into 'SpatialIndex::query' to contain the
     // entities.
     let mut scratch_buffer = Vec::with_capacity(32);
-    // A HashSet to store checked pairs.
-    let mut checked_pairs = std::collections::HashSet::new();
 
     // This loop handles Reactive vs Reactive Colliders.
     for i in 0..reactives.len() {
         let (pos_i_x, pos_i_y, col_i_rad) = (reactives[i].2 .0, reactives[i].2 .1, reactives[i].3.rad);
 
-        // Declare a search radius and define a 'Rect' to query into the quadtree using.
+        // Declare a search radius to query the spatial index with.
         let search_radius = col_i_rad * 3.0;
-        let query_rect = Rect::new(pos_i_x, pos_i_y, search_radius, search_radius);
 
         // Clear the scratch buffer.
         scratch_buffer.clear();
 
-        // Query the quadtree, passing the 'Rect' to define the query bounds and the
-        // scratch buffer to contain the resolved entities.
-        qtree.query(&query_rect, &mut scratch_buffer);
+        // Query the spatial index, passing the scratch buffer to contain the resolved
+        // entries.
+        index.query_radius(pos_i_x, pos_i_y, search_radius, &mut scratch_buffer);
 
         // Loop through each entry in the scratch buffer.
-        for nearby_point in &scratch_buffer {
-            let j = nearby_point.index;
+        for &entry in &scratch_buffer {
+            let Some(&j) = slots.get(&index.entries[entry].entity) else {
+                continue;
+            };
 
             if i >= j {
                 continue;
             }
-            if !checked_pairs.insert((i, j)) {
-                continue;
-            }
-
             // Wrapped in 'unsafe' to try and squeeze performance.
             unsafe {
                 // Get the pointer to the reactive's Vec.
@@ -171,16 +168,28 @@ pub fn collision_resolution_system(
         }
     }
 
-    // Collect wall entities outside the loop.
-    let walls: Vec<_> = non_reactive_query
-        .iter()
-        .map(|(pos, col)| ((pos.0, pos.1), col.clone()))
-        .collect();
-
     // This threaded loop handles Reactive vs Non Reactive colliders.
     // Very similar to the process done before.
-    reactive_query.par_iter_mut().for_each(|(_, mut pos, col)| {
-        for ((wx, wy), wall_col) in &walls {
+    reactive_query.par_iter_mut().for_each(|(entity, mut pos, col, clan)| {
+        let mut nearby = Vec::new();
+        index.query_radius(pos.0, pos.1, col.rad, &mut nearby);
+
+        for entry in nearby.iter().map(|&i| &index.entries[i]) {
+            if entry.reactive {
+                continue;
+            }
+            let Ok((wall_pos, wall_col, owner)) = non_reactive_query.get(entry.entity) else {
+                continue;
+            };
+
+            // Structures let their owner and the owner's allies through.
+            if let Some(owner) = owner {
+                if owner.0 == entity || are_allies(clan, clans.get(owner.0).ok()) {
+                    continue;
+                }
+            }
+            let (wx, wy) = (wall_pos.0, wall_pos.1);
+
             let dx = wx - pos.0;
             let dy = wy - pos.1;
 
@@ -189,7 +198,7 @@ pub fn collision_resolution_system(
             let combined_y = col.half_extents.1 + wall_col.half_extents.1;
             if dx.abs() < combined_x && dy.abs() < combined_y {
                 let iso_a = Isometry::translation(pos.0, pos.1);
-                let iso_b = Isometry::translation(*wx, *wy);
+                let iso_b = Isometry::translation(wx, wy);
 
                 if let Ok(Some(contact)) =
                     contact(&iso_a, col.shape.as_ref(), &iso_b, wall_col.shape.as_ref(), precision)
@@ -204,3 +213,49 @@ pub fn collision_resolution_system(
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        schedule::{IntoScheduleConfigs, Schedule},
+        world::World,
+    };
+
+    use super::*;
+    use crate::{structs::components::spawn_wall, systems::spatial_index_system};
+
+    /// Spawns a player overlapping the top edge of a wall at the origin.
+    fn spawn_player(world: &mut World, x: f32, clan: Option<u32>) -> Entity {
+        let mut player = world.spawn((Position(x, -30.), Collider::circle(35.), ReactiveCollider));
+        if let Some(clan) = clan {
+            player.insert(ClanId(clan));
+        }
+        player.id()
+    }
+
+    #[test]
+    fn allies_pass_through_owned_structures() {
+        let mut world = World::new();
+        world.insert_resource(SpatialIndex::default());
+
+        // Far enough apart not to push each other.
+        let owner = spawn_player(&mut world, -100., Some(1));
+        let ally = spawn_player(&mut world, 0., Some(1));
+        let stranger = spawn_player(&mut world, 100., Some(2));
+        let loner = spawn_player(&mut world, 200., None);
+
+        let wall = spawn_wall(&mut world, 0., 0., 300., 20.);
+        world.entity_mut(wall).insert(Owner(owner));
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems((spatial_index_system, collision_resolution_system).chain());
+        schedule.run(&mut world);
+
+        let y = |world: &World, e: Entity| world.get::<Position>(e).unwrap().1;
+        assert_eq!(y(&world, owner), -30.);
+        assert_eq!(y(&world, ally), -30.);
+        // Pushed out of the wall.
+        assert!(y(&world, stranger) <= -54.9);
+        assert!(y(&world, loner) <= -54.9);
+    }
+}
@@ -0,0 +1,20 @@
+use bevy_ecs::{
+    entity::Entity,
+    query::With,
+    system::{Query, ResMut},
+};
+
+use crate::structs::components::{Deaths, Health, PlayerEntity};
+
+/// Player Death System fn.
+/// Collects the players that died this tick, they are announced and removed
+/// once the tick is over.
+pub fn player_death_system(query: Query<(Entity, &Health), With<PlayerEntity>>, mut deaths: ResMut<Deaths>) {
+    deaths.0.clear();
+
+    for (entity, health) in query.iter() {
+        if health.0 <= 0. {
+            deaths.0.push(entity);
+        }
+    }
+}
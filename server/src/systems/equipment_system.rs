@@ -0,0 +1,93 @@
+use bevy_ecs::{
+    entity::Entity,
+    system::{Query, Res, ResMut},
+    world::World,
+};
+
+use crate::{
+    errors::ClientProducedError,
+    structs::{
+        clock::WorldClock,
+        components::{Equipment, Health, HealthUpdates, OwnedItems, ResourceUpdates, Resources},
+        store::{get_regen, get_store_item, StoreKind},
+    },
+};
+
+// ms between two regen steps.
+const REGEN_INTERVAL: u32 = 1000;
+
+/// Equipment System fn.
+/// Applies the health regen, or drain, of equipped store items.
+pub fn equipment_system(
+    mut query: Query<(Entity, &Equipment, &mut Health)>,
+    clock: Res<WorldClock>,
+    mut health_updates: ResMut<HealthUpdates>,
+) {
+    if !clock.every(REGEN_INTERVAL) {
+        return;
+    }
+
+    for (entity, equipment, mut health) in query.iter_mut() {
+        let regen = get_regen(equipment);
+
+        // Dead players don't regen, and draining can't kill.
+        if regen == 0. || health.0 <= 0. {
+            continue;
+        }
+
+        let new_health = (health.0 + regen).clamp(1_f32.min(health.0), health.1);
+        if new_health != health.0 {
+            health.0 = new_health;
+            health_updates.0.push((entity, new_health));
+        }
+    }
+}
+
+/// Validates and performs the purchase of a store item, paid with gold.
+pub fn buy_store_item(world: &mut World, entity: Entity, kind: u8, id: u8) -> Result<(), ClientProducedError> {
+    let kind = StoreKind::from_u8(kind).ok_or(ClientProducedError::InvalidStoreAction("unknown kind"))?;
+    let item = get_store_item(kind, id).ok_or(ClientProducedError::InvalidStoreAction("unknown item"))?;
+
+    let owned = world
+        .get::<OwnedItems>(entity)
+        .ok_or(ClientProducedError::InvalidStoreAction("not a player"))?;
+    if owned.get(kind).contains(&id) {
+        return Err(ClientProducedError::InvalidStoreAction("already owned"));
+    }
+
+    let mut resources = world
+        .get_mut::<Resources>(entity)
+        .ok_or(ClientProducedError::InvalidStoreAction("not a player"))?;
+    if resources.3 < item.price {
+        return Err(ClientProducedError::InvalidStoreAction("not enough gold"));
+    }
+    resources.3 -= item.price;
+
+    if let Some(mut owned) = world.get_mut::<OwnedItems>(entity) {
+        owned.get_mut(kind).push(id);
+    }
+    world.resource_mut::<ResourceUpdates>().0.push(entity);
+
+    Ok(())
+}
+
+/// Validates and equips an owned store item. 'None' unequips the slot.
+pub fn equip_store_item(world: &mut World, entity: Entity, kind: u8, id: Option<u8>) -> Result<(), ClientProducedError> {
+    let kind = StoreKind::from_u8(kind).ok_or(ClientProducedError::InvalidStoreAction("unknown kind"))?;
+
+    let owned = world
+        .get::<OwnedItems>(entity)
+        .ok_or(ClientProducedError::InvalidStoreAction("not a player"))?;
+    if id.is_some_and(|id| !owned.get(kind).contains(&id)) {
+        return Err(ClientProducedError::InvalidStoreAction("not owned"));
+    }
+
+    if let Some(mut equipment) = world.get_mut::<Equipment>(entity) {
+        match kind {
+            StoreKind::Hat => equipment.0 = id,
+            StoreKind::Accessory => equipment.1 = id,
+        }
+    }
+
+    Ok(())
+}
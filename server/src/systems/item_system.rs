@@ -0,0 +1,71 @@
+use bevy_ecs::{
+    entity::Entity,
+    query::With,
+    system::{Query, ResMut},
+};
+use shared::objects::UsableGameObjects;
+
+use crate::structs::{
+    clock::TICK_MS,
+    components::{Health, HealthUpdates, ItemCooldowns, PlayerEntity, ResourceUpdates, Resources, UseItemState},
+    objects::{get_item_cooldown, get_item_food_cost, get_item_heal},
+};
+
+/// Item System fn.
+/// Handles players using consumable items.
+pub fn item_system(
+    mut query: Query<
+        (
+            Entity,
+            &mut UseItemState,
+            &mut ItemCooldowns,
+            &mut Health,
+            &mut Resources,
+        ),
+        With<PlayerEntity>,
+    >,
+    mut health_updates: ResMut<HealthUpdates>,
+    mut resource_updates: ResMut<ResourceUpdates>,
+) {
+    for (entity, mut use_item, mut cooldowns, mut health, mut resources) in query.iter_mut() {
+        // Tick down the cooldowns of every item.
+        for cooldown in cooldowns.0.iter_mut() {
+            *cooldown = cooldown.saturating_sub(TICK_MS as u32);
+        }
+
+        // Take the requested item, if any. Requests are never queued.
+        let Some(item) = use_item.0.take() else {
+            continue;
+        };
+
+        // Unknown items are ignored.
+        let Some(item) = UsableGameObjects::from_u8(item) else {
+            continue;
+        };
+
+        // The item is still on cooldown.
+        let cooldown = &mut cooldowns.0[item as usize];
+        if *cooldown > 0 {
+            continue;
+        }
+
+        // Not enough food to use the item.
+        let cost = get_item_food_cost(&item);
+        if resources.2 < cost {
+            continue;
+        }
+
+        // Don't waste the item if there is nothing to heal.
+        if health.0 >= health.1 {
+            continue;
+        }
+
+        resources.2 -= cost;
+        health.0 = (health.0 + get_item_heal(&item)).min(health.1);
+        *cooldown = get_item_cooldown(&item);
+
+        // Tell the clients about the new health and resources.
+        health_updates.0.push((entity, health.0));
+        resource_updates.0.push(entity);
+    }
+}
@@ -2,61 +2,345 @@ use std::f32;
 
 use bevy_ecs::world::World;
 use nanorand::Rng;
+use noise::{NoiseFn, Perlin};
+use shared::objects::{GameObjects, StaticGameObjects};
 
 use crate::{
-    structs::components::{AimDir, Health, ObjectBundle, ObjectEntity, Position, Resources},
-    systems::{Collider, GlobalRng, NonReactiveCollider},
+    config::config::BiomeEffects,
+    structs::{
+        components::{AimDir, Health, ObjectBundle, ObjectEntity, Position},
+        objects::get_object_yield,
+        rng::MapGenRng,
+        spatial_hash::SpatialHash,
+    },
+    systems::{Collider, NonReactiveCollider},
     CONFIG,
 };
 
-/// Initializes the map. Spawns game objects, initializes world boundaries.
-pub fn init_map(world: &mut World, rng: &mut GlobalRng) {
-    // Spawn trees
-    for i in 0..1000 {
-        // Generate random x and y coordinates.
-        let x = rng.0.generate::<f32>() * CONFIG.map.size as f32;
-        let y = rng.0.generate::<f32>() * CONFIG.map.size as f32;
-
-        // Prevent spawning in the ocean biome.
-        if x > CONFIG.map.ocean_start_x as f32 || (y > CONFIG.map.lava_start as f32 || y < CONFIG.map.snow_start as f32)
-        {
-            continue;
+// TODO: move to config file.
+const DENSITY_FREQUENCY: f64 = 0.0003;
+const VARIETY_FREQUENCY: f64 = 0.002;
+const PLACEMENT_ATTEMPTS: u32 = 12;
+const BORDER_MARGIN: f32 = 200.;
+const GOLD_SPACING: f32 = 1500.;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+/// The biomes of the map. Snow, grassland, desert and lava are stacked
+/// vertically, the ocean covers the right side of the map.
+pub enum Biome {
+    Snow = 0,
+    Grassland = 1,
+    Desert = 2,
+    Lava = 3,
+    Ocean = 4,
+}
+
+impl Biome {
+    pub const ALL: [Biome; 5] = [Biome::Snow, Biome::Grassland, Biome::Desert, Biome::Lava, Biome::Ocean];
+
+    /// Returns the effects of the biome on the players in it.
+    pub fn effects(self) -> &'static BiomeEffects {
+        let biomes = &CONFIG.biomes;
+        match self {
+            Biome::Snow => &biomes.snow,
+            Biome::Grassland => &biomes.grassland,
+            Biome::Desert => &biomes.desert,
+            Biome::Lava => &biomes.lava,
+            Biome::Ocean => &biomes.ocean,
         }
+    }
+}
 
-        // Prepare the game object bundle entry.
-        let bundle: ObjectBundle;
-        if i % 3 == 0 {
-            bundle = ObjectBundle(
-                ObjectEntity,
-                shared::objects::GameObjects::StaticGameObjects(shared::objects::StaticGameObjects::Stone),
-                Position(x, y),
-                AimDir(rng.0.generate::<f32>() * f32::consts::PI * 2.),
-                Health(6767., 6767.),
-                Resources(1, 0, 0, 0, 0),
-                Collider::circle(rng.0.generate::<f32>() * 30. + 30.),
-                super::NonReactiveCollider,
-            );
-        } else {
-            bundle = ObjectBundle(
-                ObjectEntity,
-                shared::objects::GameObjects::StaticGameObjects(shared::objects::StaticGameObjects::Tree),
-                Position(x, y),
-                AimDir(rng.0.generate::<f32>() * f32::consts::PI * 2.),
-                Health(6767., 6767.),
-                Resources(0, 1, 0, 0, 0),
-                Collider::circle(rng.0.generate::<f32>() * 60. + 60.),
-                super::NonReactiveCollider,
+/// Returns the biome at the given coordinates.
+pub fn get_biome(x: f32, y: f32) -> Biome {
+    let map = &CONFIG.map;
+
+    if x >= map.ocean_start_x as f32 && x <= map.ocean_end_x as f32 {
+        Biome::Ocean
+    } else if y < map.snow_end as f32 {
+        Biome::Snow
+    } else if y < map.grassland_end as f32 {
+        Biome::Grassland
+    } else if y < map.desert_end as f32 {
+        Biome::Desert
+    } else {
+        Biome::Lava
+    }
+}
+
+/// A game object that has been placed by the generator, but not yet spawned.
+struct Placement {
+    object: GameObjects,
+    x:      f32,
+    y:      f32,
+    radius: f32,
+}
+
+/// Keeps track of every placement, and rejects new ones that are too close to
+/// the existing ones.
+struct Placements {
+    placed:  Vec<Placement>,
+    hash:    SpatialHash,
+    spacing: f32,
+    found:   Vec<usize>,
+}
+
+impl Placements {
+    fn new(spacing: f32) -> Self {
+        Self {
+            placed: Vec::new(),
+            hash: SpatialHash::new(256.),
+            spacing,
+            found: Vec::with_capacity(32),
+        }
+    }
+
+    /// Returns true if an object of the radius fits at the coordinates, keeping
+    /// the minimum spacing between it and every other object.
+    fn fits(&mut self, x: f32, y: f32, radius: f32) -> bool {
+        let gap = self.spacing;
+
+        // The biggest object radius is 120, use that as the search bound.
+        self.found.clear();
+        self.hash.query(x, y, radius + gap + 120., &mut self.found);
+
+        self.found.iter().all(|&i| {
+            let other = &self.placed[i];
+            let dx = other.x - x;
+            let dy = other.y - y;
+            let min_dist = other.radius + radius + gap;
+            dx * dx + dy * dy >= min_dist * min_dist
+        })
+    }
+
+    fn push(&mut self, placement: Placement) {
+        self.hash.insert(placement.x, placement.y, self.placed.len());
+        self.placed.push(placement);
+    }
+}
+
+/// Initializes the map. Spawns game objects, initializes world boundaries.
+pub fn init_map(world: &mut World, rng: &mut MapGenRng) {
+    // Noise fields. Density decides how many objects a chunk gets, variety decides
+    // which object type goes where.
+    let density = Perlin::new(rng.0.generate::<u32>());
+    let variety = Perlin::new(rng.0.generate::<u32>());
+
+    let chunk_size = CONFIG.entities.chunk_size as f32;
+    let chunks = (CONFIG.map.size as f32 / chunk_size).ceil() as u32;
+
+    let mut placements = Placements::new(CONFIG.entities.object_spacing);
+
+    // Generate each chunk.
+    for cx in 0..chunks {
+        for cy in 0..chunks {
+            generate_chunk(
+                &mut placements,
+                rng,
+                &density,
+                &variety,
+                cx as f32 * chunk_size,
+                cy as f32 * chunk_size,
+                chunk_size,
             );
         }
+    }
+
+    // Gold is rare, a fixed amount is scattered over the whole map.
+    generate_gold(&mut placements, rng);
 
-        // Spawn the entity in the bevy world.
-        world.spawn(bundle);
+    tracing::info!("generated {} game objects", placements.placed.len());
+
+    // Spawn the entities in the bevy world.
+    for placement in placements.placed {
+        // The resources yielded per hit are looked up from the yield table.
+        let yields = get_object_yield(&placement.object);
+
+        world.spawn(ObjectBundle(
+            ObjectEntity,
+            placement.object,
+            Position(placement.x, placement.y),
+            AimDir(rng.0.generate::<f32>() * f32::consts::PI * 2.),
+            Health(6767., 6767.),
+            yields,
+            Collider::circle(placement.radius),
+            NonReactiveCollider,
+        ));
     }
 
     // Initialize wall boundaries.
     init_wall_boundaries(world);
 }
 
+/// Generates the game objects of a single chunk.
+fn generate_chunk(
+    placements: &mut Placements,
+    rng: &mut MapGenRng,
+    density: &Perlin,
+    variety: &Perlin,
+    chunk_x: f32,
+    chunk_y: f32,
+    chunk_size: f32,
+) {
+    let center_x = chunk_x + chunk_size * 0.5;
+    let center_y = chunk_y + chunk_size * 0.5;
+
+    // The ocean is kept empty.
+    let biome = get_biome(center_x, center_y);
+    if biome == Biome::Ocean {
+        return;
+    }
+
+    // Map the density noise from [-1, 1] to [0.25, 1.75].
+    let density =
+        density.get([center_x as f64 * DENSITY_FREQUENCY, center_y as f64 * DENSITY_FREQUENCY]) as f32 * 0.75 + 1.;
+
+    // Stones get more common the closer the chunk is to the lava.
+    let desert_height = (CONFIG.map.desert_end - CONFIG.map.desert_start) as f32;
+    let lava_proximity = 1. - ((CONFIG.map.lava_start as f32 - center_y) / desert_height).clamp(0., 1.);
+
+    let entities = &CONFIG.entities;
+    let (trees, bushes, stones) = match biome {
+        Biome::Snow => (entities.trees_per_chunk as f32, 0., entities.stones_per_chunk as f32),
+        Biome::Grassland => (
+            entities.trees_per_chunk as f32,
+            entities.bushes_per_chunk as f32,
+            entities.stones_per_chunk as f32,
+        ),
+        Biome::Desert => (
+            0.,
+            entities.bushes_per_chunk as f32,
+            entities.stones_per_chunk as f32 * (1. + lava_proximity),
+        ),
+        Biome::Lava => (0., 0., entities.stones_per_chunk as f32 * 3.),
+        Biome::Ocean => unreachable!(),
+    };
+
+    let counts = [
+        (ObjectKind::Tree, (trees * density).round() as u32),
+        (ObjectKind::Bush, (bushes * density).round() as u32),
+        (ObjectKind::Stone, (stones * density).round() as u32),
+    ];
+
+    for (kind, count) in counts {
+        for _ in 0..count {
+            for _ in 0..PLACEMENT_ATTEMPTS {
+                let x = chunk_x + rng.0.generate::<f32>() * chunk_size;
+                let y = chunk_y + rng.0.generate::<f32>() * chunk_size;
+
+                // Objects must stay in the same biome as their chunk, away from the
+                // walls.
+                if get_biome(x, y) != biome || !in_bounds(x, y) {
+                    continue;
+                }
+
+                // Rocky patches of the map turn trees into stones.
+                let rocky = variety.get([x as f64 * VARIETY_FREQUENCY, y as f64 * VARIETY_FREQUENCY]) > 0.55;
+                let kind = if kind == ObjectKind::Tree && rocky {
+                    ObjectKind::Stone
+                } else {
+                    kind
+                };
+
+                let (object, radius) = roll_object(rng, kind, biome);
+                if placements.fits(x, y, radius) {
+                    placements.push(Placement { object, x, y, radius });
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Scatters the world's gold over random land positions.
+fn generate_gold(placements: &mut Placements, rng: &mut MapGenRng) {
+    let size = CONFIG.map.size as f32;
+    let mut remaining = CONFIG.entities.gold_in_world;
+
+    // Bound the amount of attempts, the map could be too crowded.
+    for _ in 0..(remaining as u32 * PLACEMENT_ATTEMPTS * 10) {
+        if remaining == 0 {
+            break;
+        }
+
+        let x = rng.0.generate::<f32>() * size;
+        let y = rng.0.generate::<f32>() * size;
+        if get_biome(x, y) == Biome::Ocean || !in_bounds(x, y) {
+            continue;
+        }
+
+        let radius = rng.0.generate::<f32>() * 20. + 60.;
+        if !placements.fits(x, y, radius) || !far_from_gold(placements, x, y) {
+            continue;
+        }
+
+        placements.push(Placement {
+            object: GameObjects::StaticGameObjects(StaticGameObjects::Gold),
+            x,
+            y,
+            radius,
+        });
+        remaining -= 1;
+    }
+
+    if remaining > 0 {
+        tracing::warn!("could only place {} gold", CONFIG.entities.gold_in_world - remaining);
+    }
+}
+
+/// Returns true if the coordinates are at least 'GOLD_SPACING' units away from
+/// every placed gold.
+fn far_from_gold(placements: &Placements, x: f32, y: f32) -> bool {
+    placements.placed.iter().all(|p| {
+        !matches!(p.object, GameObjects::StaticGameObjects(StaticGameObjects::Gold))
+            || (p.x - x).powi(2) + (p.y - y).powi(2) >= GOLD_SPACING * GOLD_SPACING
+    })
+}
+
+/// Returns true if the coordinates are far enough from the world boundaries.
+fn in_bounds(x: f32, y: f32) -> bool {
+    let size = CONFIG.map.size as f32;
+    x > BORDER_MARGIN && x < size - BORDER_MARGIN && y > BORDER_MARGIN && y < size - BORDER_MARGIN
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+/// The generic kind of object, resolved into a biome specific game object.
+enum ObjectKind {
+    Tree,
+    Bush,
+    Stone,
+}
+
+/// Resolves the object kind into the game object of the biome, and rolls its
+/// radius.
+fn roll_object(rng: &mut MapGenRng, kind: ObjectKind, biome: Biome) -> (GameObjects, f32) {
+    let roll = rng.0.generate::<f32>();
+
+    match (kind, biome) {
+        (ObjectKind::Tree, Biome::Snow) => (
+            GameObjects::StaticGameObjects(StaticGameObjects::Pine),
+            roll * 50. + 60.,
+        ),
+        (ObjectKind::Tree, _) => (
+            GameObjects::StaticGameObjects(StaticGameObjects::Tree),
+            roll * 60. + 60.,
+        ),
+        (ObjectKind::Bush, Biome::Desert) => (
+            GameObjects::StaticGameObjects(StaticGameObjects::Cactus),
+            roll * 15. + 35.,
+        ),
+        (ObjectKind::Bush, _) => (
+            GameObjects::StaticGameObjects(StaticGameObjects::Bush),
+            roll * 15. + 35.,
+        ),
+        (ObjectKind::Stone, _) => (
+            GameObjects::StaticGameObjects(StaticGameObjects::Stone),
+            roll * 30. + 30.,
+        ),
+    }
+}
+
 /// Initializes wall boundaries.
 // TODO: fix boundary Collider entries, they are not correct.
 fn init_wall_boundaries(world: &mut World) {
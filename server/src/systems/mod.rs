@@ -1,12 +1,30 @@
 mod animal_ai_system;
+mod anticheat_system;
 mod attack_system;
+mod biome_system;
+mod clock_system;
 mod collision_system;
 mod damage_system;
+mod death_system;
+mod equipment_system;
+mod item_system;
 mod map_system;
 mod movement_system;
+mod population_system;
+mod progression_system;
+mod spatial_index_system;
 
 pub use animal_ai_system::*;
+pub use anticheat_system::anticheat_system;
 pub use attack_system::*;
+pub use biome_system::biome_system;
+pub use clock_system::clock_system;
 pub use collision_system::*;
+pub use death_system::player_death_system;
+pub use equipment_system::*;
+pub use item_system::item_system;
 pub use map_system::*;
 pub use movement_system::movement_system;
+pub use population_system::*;
+pub use progression_system::*;
+pub use spatial_index_system::*;
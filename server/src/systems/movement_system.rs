@@ -1,43 +1,57 @@
 use bevy_ecs::prelude::*;
 
 use crate::{
-    structs::components::{AttackState, MoveDir, PlayerEntity, Position, ReloadState, Velocity},
-    CONFIG,
+    structs::{
+        components::{AttackState, Equipment, MoveDir, PlayerEntity, Position, ReloadState, Velocity},
+        store::get_speed_multiplier,
+    },
+    systems::get_biome,
 };
 
 // TODO: move into config file.
 const PLAYER_MAX_SPEED: f32 = 45.0;
 const PLAYER_ACCEL: f32 = 120.0;
 const PLAYER_FRICTION: f32 = 90.0;
-const SNOW_FRICTION: f32 = 67.0;
 const DT: f32 = 0.45;
 
+/// Components read and written by the movement system.
+type MovementData = (
+    &'static mut Position,
+    &'static mut Velocity,
+    &'static MoveDir,
+    &'static ReloadState,
+    &'static AttackState,
+    &'static Equipment,
+);
+
 /// System that handles player movement.
 pub fn movement_system(
-    mut query: Query<(&mut Position, &mut Velocity, &MoveDir, &ReloadState, &AttackState), With<PlayerEntity>>,
+    mut query: Query<MovementData, With<PlayerEntity>>,
 ) {
     let dt = DT;
 
     // This threaded loop iterates through each query entry.
     query
         .par_iter_mut()
-        .for_each(|(mut pos, mut vel, move_dir, reload_state, attack_state)| {
+        .for_each(|(mut pos, mut vel, move_dir, reload_state, attack_state, equipment)| {
             let mut vx = vel.0;
             let mut vy = vel.1;
 
+            // The biome the player is in changes how they move.
+            let effects = get_biome(pos.0, pos.1).effects();
+
             // Apply deceleration.
-            let mut decel = 1_f32;
-            if reload_state.0 <= 0 && attack_state.0 {
+            let mut decel = effects.speed;
+            if reload_state.0 == 0 && attack_state.0 {
                 decel *= 0.;
             }
-            if pos.0 > CONFIG.map.ocean_start_x as f32 {
-                decel *= 0.6;
-            }
 
             // If the player is moving in any direction.
             if let Some(dir) = move_dir.0 {
-                let target_vx = dir.cos() * PLAYER_MAX_SPEED;
-                let target_vy = dir.sin() * PLAYER_MAX_SPEED;
+                // Equipment can speed up, or slow down, the player.
+                let max_speed = PLAYER_MAX_SPEED * get_speed_multiplier(equipment);
+                let target_vx = dir.cos() * max_speed;
+                let target_vy = dir.sin() * max_speed;
 
                 let dx = target_vx - vx;
                 let dy = target_vy - vy;
@@ -57,13 +71,7 @@ pub fn movement_system(
                 vy *= decel;
             } else {
                 let speed = (vx * vx + vy * vy).sqrt();
-                let mut drop = 0_f32;
-
-                if pos.1 < 4096. {
-                    drop = SNOW_FRICTION * dt;
-                } else {
-                    drop = PLAYER_FRICTION * dt;
-                }
+                let drop = PLAYER_FRICTION * effects.friction * dt;
 
                 if speed > drop {
                     vx -= vx / speed * drop;
@@ -77,7 +85,8 @@ pub fn movement_system(
             vel.0 = vx;
             vel.1 = vy;
 
-            pos.0 += vx * dt;
-            pos.1 += vy * dt;
+            // Currents move the player without changing their velocity.
+            pos.0 += (vx + effects.current_x) * dt;
+            pos.1 += (vy + effects.current_y) * dt;
         });
 }
@@ -0,0 +1,112 @@
+use bevy_ecs::{
+    query::With,
+    system::{Commands, Query, Res, ResMut},
+    world::World,
+};
+use nanorand::{Rng, WyRand};
+
+use crate::{
+    structs::{
+        clock::{WorldClock, TICK_MS},
+        components::{spawn_animal, AnimalEntity, Habitat, PendingSpawns},
+        rng::{MapGenRng, SpawnRng},
+        spatial_index::{EntryKind, SpatialIndex},
+        spawns::{get_respawn_delay, get_zone_target, SpawnZone, SPAWN_ZONES},
+    },
+    systems::{animal_bounds, get_biome},
+    CONFIG,
+};
+
+const SPAWN_ATTEMPTS: u32 = 16;
+
+/// Population System fn.
+/// Keeps every spawn zone at its target, dead animals are replaced after the
+/// respawn delay of their species.
+pub fn population_system(
+    mut commands: Commands,
+    mut rng: ResMut<SpawnRng>,
+    mut pending: ResMut<PendingSpawns>,
+    animals: Query<&Habitat, With<AnimalEntity>>,
+    index: Res<SpatialIndex>,
+    clock: Res<WorldClock>,
+) {
+    // Count the living animals and the queued respawns of every zone.
+    let mut counts = vec![0; SPAWN_ZONES.len()];
+    for habitat in animals.iter() {
+        counts[habitat.0 as usize] += 1;
+    }
+    for (zone, _) in pending.0.iter() {
+        counts[*zone as usize] += 1;
+    }
+
+    // Queue a respawn for every missing animal. Animals come back faster at
+    // night.
+    let factor = if clock.is_night() {
+        CONFIG.time.night_respawn_factor
+    } else {
+        1.
+    };
+    for (i, zone) in SPAWN_ZONES.iter().enumerate() {
+        for _ in counts[i]..get_zone_target(zone) {
+            let delay = (get_respawn_delay(zone.animal) as f32 * factor) as u32;
+            pending.0.push((i as u8, delay));
+        }
+    }
+
+    // Spawn the due animals. When no spot is found, try again next tick.
+    pending.0.retain_mut(|(zone, delay)| {
+        *delay = delay.saturating_sub(TICK_MS as u32);
+        if *delay > 0 {
+            return true;
+        }
+
+        let spawn_zone = &SPAWN_ZONES[*zone as usize];
+        match find_spawn(&mut rng.0, spawn_zone, Some(&index)) {
+            Some((x, y)) => {
+                spawn_animal(&mut commands, spawn_zone.animal, *zone, x, y);
+                false
+            }
+            None => true,
+        }
+    });
+}
+
+/// Fills every spawn zone up to its target.
+pub fn init_animals(world: &mut World, rng: &mut MapGenRng) {
+    let mut commands = world.commands();
+
+    for (i, zone) in SPAWN_ZONES.iter().enumerate() {
+        for _ in 0..get_zone_target(zone) {
+            if let Some((x, y)) = find_spawn(&mut rng.0, zone, None) {
+                spawn_animal(&mut commands, zone.animal, i as u8, x, y);
+            }
+        }
+    }
+
+    world.flush();
+}
+
+/// Picks a random position in the zone, away from every player in the index.
+fn find_spawn(rng: &mut WyRand, zone: &SpawnZone, index: Option<&SpatialIndex>) -> Option<(f32, f32)> {
+    let (min_x, max_x, min_y, max_y) = animal_bounds(&zone.animal);
+    let safe_range = CONFIG.animals.spawn_safe_range;
+
+    for _ in 0..SPAWN_ATTEMPTS {
+        let x = min_x + rng.generate::<f32>() * (max_x - min_x);
+        let y = min_y + rng.generate::<f32>() * (max_y - min_y);
+        if get_biome(x, y) != zone.biome {
+            continue;
+        }
+
+        let near_player = index.is_some_and(|index| {
+            !index
+                .nearest(x, y, safe_range, 1, |entry| entry.kind == EntryKind::Player)
+                .is_empty()
+        });
+        if !near_player {
+            return Some((x, y));
+        }
+    }
+
+    None
+}
@@ -0,0 +1,161 @@
+use bevy_ecs::{
+    entity::Entity,
+    query::Changed,
+    system::{Query, ResMut},
+    world::World,
+};
+
+use crate::{
+    errors::ClientProducedError,
+    structs::{
+        components::{Age, Health, ProgressionUpdates, ReloadState, Upgrades, Xp},
+        upgrades::{available_upgrades, get_upgrade, UpgradeEffect},
+        weapons::{get_weapon_attack_speed, Weapon},
+    },
+    CONFIG,
+};
+
+/// Progression System fn.
+/// Ages up players that gained enough XP.
+pub fn progression_system(
+    mut query: Query<(Entity, &mut Xp, &mut Age, &mut Upgrades), Changed<Xp>>,
+    mut progression_updates: ResMut<ProgressionUpdates>,
+) {
+    for (entity, mut xp, mut age, mut upgrades) in query.iter_mut() {
+        // Age up as many times as the XP allows.
+        while xp.0 >= xp.1 && age.0 < CONFIG.progression.max_age {
+            xp.0 -= xp.1;
+            xp.1 = (xp.1 as f32 * CONFIG.progression.xp_growth).round() as u32;
+            age.0 += 1;
+            upgrades.0 += 1;
+        }
+
+        // Tell the player about their XP, and possibly new upgrades.
+        progression_updates.0.push(entity);
+    }
+}
+
+/// Validates and applies the upgrade chosen by the player.
+/// Returns the new weapon if the upgrade granted one.
+pub fn choose_upgrade(world: &mut World, entity: Entity, id: u8) -> Result<Option<Weapon>, ClientProducedError> {
+    let upgrade = get_upgrade(id).ok_or(ClientProducedError::InvalidUpgrade("unknown upgrade"))?;
+
+    let (Some(age), Some(upgrades)) = (world.get::<Age>(entity), world.get::<Upgrades>(entity)) else {
+        return Err(ClientProducedError::InvalidUpgrade("not a player"));
+    };
+
+    if upgrades.0 == 0 {
+        return Err(ClientProducedError::InvalidUpgrade("no upgrade points"));
+    }
+    if !available_upgrades(age.0, &upgrades.1).contains(&id) {
+        return Err(ClientProducedError::InvalidUpgrade("upgrade not available"));
+    }
+
+    let mut weapon = None;
+    match upgrade.effect {
+        UpgradeEffect::Weapon(w) => {
+            if let Some(mut current) = world.get_mut::<Weapon>(entity) {
+                *current = w;
+            }
+            if let Some(mut reload) = world.get_mut::<ReloadState>(entity) {
+                reload.1 = get_weapon_attack_speed(&w) as u32;
+            }
+            weapon = Some(w);
+        }
+        UpgradeEffect::MaxHealth(amount) => {
+            if let Some(mut health) = world.get_mut::<Health>(entity) {
+                health.1 += amount;
+                health.0 += amount;
+            }
+        }
+    }
+
+    if let Some(mut upgrades) = world.get_mut::<Upgrades>(entity) {
+        upgrades.0 -= 1;
+        upgrades.1.push(id);
+    }
+
+    Ok(weapon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::upgrades::get_upgrade;
+
+    /// Spawns a player of the age with the unspent upgrade points.
+    fn spawn_player(world: &mut World, age: u8, points: u8) -> Entity {
+        world
+            .spawn((
+                Age(age),
+                Upgrades(points, Vec::new()),
+                Weapon::Fists,
+                ReloadState(0, 300),
+                Health(100., 100.),
+            ))
+            .id()
+    }
+
+    fn is_invalid(result: Result<Option<Weapon>, ClientProducedError>, reason: &str) -> bool {
+        matches!(result, Err(ClientProducedError::InvalidUpgrade(r)) if r == reason)
+    }
+
+    #[test]
+    fn applies_the_upgrade() {
+        let mut world = World::new();
+        let player = spawn_player(&mut world, 2, 1);
+        let id = available_upgrades(2, &[])[0];
+
+        let weapon = choose_upgrade(&mut world, player, id).unwrap();
+
+        let upgrades = world.get::<Upgrades>(player).unwrap();
+        assert_eq!((upgrades.0, upgrades.1.clone()), (0, vec![id]));
+        if let UpgradeEffect::Weapon(w) = get_upgrade(id).unwrap().effect {
+            assert_eq!(weapon, Some(w));
+            assert_eq!(world.get::<Weapon>(player), Some(&w));
+            assert_eq!(
+                world.get::<ReloadState>(player).unwrap().1,
+                get_weapon_attack_speed(&w) as u32
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_upgrades_of_a_later_age() {
+        let mut world = World::new();
+        let player = spawn_player(&mut world, 2, 1);
+        let later = CONFIG.progression.upgrades.iter().find(|u| u.age > 2).unwrap();
+
+        assert!(is_invalid(
+            choose_upgrade(&mut world, player, later.id),
+            "upgrade not available"
+        ));
+        assert_eq!(world.get::<Upgrades>(player).unwrap().0, 1);
+    }
+
+    #[test]
+    fn rejects_spent_points() {
+        let mut world = World::new();
+        let player = spawn_player(&mut world, 2, 1);
+        let available = available_upgrades(2, &[]);
+        choose_upgrade(&mut world, player, available[0]).unwrap();
+
+        assert!(is_invalid(
+            choose_upgrade(&mut world, player, available[1]),
+            "no upgrade points"
+        ));
+        assert_eq!(world.get::<Upgrades>(player).unwrap().1, vec![available[0]]);
+    }
+
+    #[test]
+    fn rejects_unknown_upgrades() {
+        let mut world = World::new();
+        let player = spawn_player(&mut world, CONFIG.progression.max_age, 1);
+        let unknown = (0..=u8::MAX).find(|&id| get_upgrade(id).is_none()).unwrap();
+
+        assert!(is_invalid(
+            choose_upgrade(&mut world, player, unknown),
+            "unknown upgrade"
+        ));
+    }
+}
@@ -0,0 +1,48 @@
+use bevy_ecs::{
+    entity::Entity,
+    query::Has,
+    system::{Query, ResMut},
+};
+
+use crate::{
+    structs::{
+        components::{AnimalType, ObjectEntity, PlayerEntity, Position, Velocity},
+        spatial_index::{EntryKind, IndexEntry, SpatialIndex},
+    },
+    systems::{Collider, ReactiveCollider},
+};
+
+type IndexedData<'a> = (
+    Entity,
+    &'a Position,
+    &'a Collider,
+    Option<&'a Velocity>,
+    Option<&'a AnimalType>,
+    Has<PlayerEntity>,
+    Has<ObjectEntity>,
+    Has<ReactiveCollider>,
+);
+
+/// Spatial Index System fn.
+/// Rebuilds the spatial index from the positions of this tick.
+pub fn spatial_index_system(mut index: ResMut<SpatialIndex>, query: Query<IndexedData>) {
+    index.clear();
+
+    for (entity, pos, collider, vel, animal, player, object, reactive) in query.iter() {
+        let kind = match (animal, player, object) {
+            (Some(animal), _, _) => EntryKind::Animal(*animal),
+            (_, true, _) => EntryKind::Player,
+            (_, _, true) => EntryKind::Object,
+            _ => EntryKind::Wall,
+        };
+
+        index.insert(IndexEntry {
+            entity,
+            kind,
+            pos: *pos,
+            vel: vel.copied().unwrap_or(Velocity(0., 0.)),
+            radius: collider.rad,
+            reactive,
+        });
+    }
+}
@@ -44,6 +44,33 @@ pub enum PacketType {
     SetWeapons = 9,
     ObjectHitAnim = 10,
     SetResource = 11,
+    UseItem = 12,
+    UpdateAge = 13,
+    UpgradeOptions = 14,
+    ChooseUpgrade = 15,
+    StoreBuy = 16,
+    StoreEquip = 17,
+    StoreUpdate = 18,
+    Biomes = 19,
+    TimeSync = 20,
+    Chat = 21,
+    ChatBubble = 22,
+    SystemMessage = 23,
+    ClanCreate = 24,
+    ClanJoin = 25,
+    ClanLeave = 26,
+    ClanAccept = 27,
+    ClanKick = 28,
+    ClanUpdate = 29,
+    ClanRequest = 30,
+    Leaderboard = 31,
+    KillFeed = 32,
+    Death = 33,
+    MapPing = 34,
+    Minimap = 35,
+    AllyPing = 36,
+    SpawnRejected = 37,
+    IdleWarning = 38,
 }
 
 impl PacketType {
@@ -60,6 +87,33 @@ impl PacketType {
             9 => Some(Self::SetWeapons),
             10 => Some(Self::ObjectHitAnim),
             11 => Some(Self::SetResource),
+            12 => Some(Self::UseItem),
+            13 => Some(Self::UpdateAge),
+            14 => Some(Self::UpgradeOptions),
+            15 => Some(Self::ChooseUpgrade),
+            16 => Some(Self::StoreBuy),
+            17 => Some(Self::StoreEquip),
+            18 => Some(Self::StoreUpdate),
+            19 => Some(Self::Biomes),
+            20 => Some(Self::TimeSync),
+            21 => Some(Self::Chat),
+            22 => Some(Self::ChatBubble),
+            23 => Some(Self::SystemMessage),
+            24 => Some(Self::ClanCreate),
+            25 => Some(Self::ClanJoin),
+            26 => Some(Self::ClanLeave),
+            27 => Some(Self::ClanAccept),
+            28 => Some(Self::ClanKick),
+            29 => Some(Self::ClanUpdate),
+            30 => Some(Self::ClanRequest),
+            31 => Some(Self::Leaderboard),
+            32 => Some(Self::KillFeed),
+            33 => Some(Self::Death),
+            34 => Some(Self::MapPing),
+            35 => Some(Self::Minimap),
+            36 => Some(Self::AllyPing),
+            37 => Some(Self::SpawnRejected),
+            38 => Some(Self::IdleWarning),
             _ => None,
         }
     }
@@ -8,8 +8,22 @@ pub enum GameObjects {
 }
 
 // healing
+#[derive(Clone, Copy, Debug)]
+#[repr(u8)]
 pub enum UsableGameObjects {
-    Apple,
+    Apple = 0,
+}
+
+impl UsableGameObjects {
+    /// The amount of usable game objects.
+    pub const COUNT: usize = 1;
+
+    pub fn from_u8(val: u8) -> Option<UsableGameObjects> {
+        match val {
+            0 => Some(Self::Apple),
+            _ => None,
+        }
+    }
 }
 
 pub enum PlacableGameObjects {
@@ -20,4 +34,7 @@ pub enum StaticGameObjects {
     Tree,
     Stone,
     Bush,
+    Gold,
+    Pine,
+    Cactus,
 }
@@ -30,8 +30,9 @@ impl Player {
         Self {
             name,
             id,
-            x: 10000.,
-            y: 10000.,
+            // picked by the server when the player spawns
+            x: 0.,
+            y: 0.,
             vx: 0.0, 
             vy: 0.0,
             move_dir: None,
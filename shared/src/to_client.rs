@@ -18,6 +18,8 @@ pub struct PlayerTO {
     pub y: f32,
     pub aim: f32,
     pub weapon_index: Option<u8>,
+    pub hat: Option<u8>,
+    pub accessory: Option<u8>,
 }
 
 impl From<ServerPlayer> for PlayerTO {
@@ -29,6 +31,8 @@ impl From<ServerPlayer> for PlayerTO {
             y: value.y,
             aim: value.aim,
             weapon_index: value.weapon_index,
+            hat: None,
+            accessory: None,
         }
     }
 }
@@ -119,6 +123,7 @@ pub struct SetResourceData {
     pub wood: u32,
     pub stone: u32,
     pub food: u32,
+    pub gold: u32,
 }
 
 #[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
@@ -127,4 +132,172 @@ pub struct SetWeaponsData {
     pub weapons: Vec<u8>,
 }
 
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct UpdateAgeData {
+    pub xp: u32,
+    pub xp_needed: u32,
+    pub age: u8,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct UpgradeOptionsData {
+    pub points: u8,
+    pub upgrades: Vec<u8>,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct StoreUpdateData {
+    pub kind: u8,
+    pub owned: Vec<u8>,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct BiomeTO {
+    pub biome: u8,
+    pub speed: f32,
+    pub friction: f32,
+    pub damage: f32,
+    pub current_x: f32,
+    pub current_y: f32,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct BiomeData {
+    pub snow_end: u16,
+    pub grassland_end: u16,
+    pub desert_end: u16,
+    pub ocean_start_x: u16,
+    pub ocean_end_x: u16,
+    pub biomes: Vec<BiomeTO>,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct TimeSyncData {
+    pub day: u32,
+    pub time: f32,
+    pub day_length: u32,
+    pub night_length: f32,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct ChatBubbleData {
+    pub id: u32,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct SystemMessageData {
+    pub message: String,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct ClanTO {
+    pub id: u32,
+    pub name: String,
+    pub owner: u32,
+    pub members: Vec<u32>,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct ClansData {
+    pub clans: Vec<ClanTO>,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct ClanRequestData {
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct LeaderboardEntryTO {
+    pub id: u32,
+    pub name: String,
+    pub score: u32,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct LeaderboardData {
+    pub entries: Vec<LeaderboardEntryTO>,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct KillFeedData {
+    pub killer: Option<u32>,
+    pub victim: u32,
+    pub cause: u8,
+    pub weapon: Option<u8>,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct DeathData {
+    pub killer: Option<u32>,
+    pub killer_name: Option<String>,
+    pub cause: u8,
+    pub weapon: Option<u8>,
+    pub age: u8,
+    pub xp: u32,
+    pub kills: u32,
+    pub gold: u32,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct MinimapAllyTO {
+    pub id: u32,
+    pub x: u8,
+    pub y: u8,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct MinimapPointTO {
+    pub kind: u8,
+    pub x: u8,
+    pub y: u8,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct MinimapData {
+    pub allies: Vec<MinimapAllyTO>,
+    pub points: Vec<MinimapPointTO>,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct AllyPingData {
+    pub id: u32,
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct SpawnRejectedData {
+    pub reason: u8,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct IdleWarningData {
+    // ms before the player is disconnected
+    pub remaining: u32,
+}
+
 // send animials packets to client and render to make sure fishes and animal systems work
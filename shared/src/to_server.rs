@@ -7,6 +7,8 @@ use crate::to_client::{AddObjectData, AddPlayerData, SetWeaponsData};
 #[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
 pub struct SpawnMessage {
     pub name: String,
+    // spawn next to a clan member when possible
+    pub near_clan: bool,
 }
 
 #[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
@@ -25,12 +27,73 @@ pub struct AimMessage {
 #[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
 pub struct HitMessage {}
 
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct UseItemMessage {
+    pub item: u8,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct ChooseUpgradeMessage {
+    pub upgrade: u8,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct StoreBuyMessage {
+    pub kind: u8,
+    pub id: u8,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct StoreEquipMessage {
+    pub kind: u8,
+    pub id: Option<u8>,
+}
+
 #[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
 #[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
 pub struct ChatMessage {
     pub message: String,
 }
 
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct ClanCreateMessage {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct ClanJoinMessage {
+    pub clan: u32,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct ClanLeaveMessage {}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct ClanAcceptMessage {
+    pub player: u32,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct ClanKickMessage {
+    pub player: u32,
+}
+
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
+pub struct MapPingMessage {
+    pub x: f32,
+    pub y: f32,
+}
+
 #[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
 #[cfg_attr(feature = "web", derive(Serialize, Deserialize))]
 pub enum ClientMessages {
@@ -3,10 +3,16 @@
 
 use serde::Serialize;
 use shared::to_client::{
-    AddAnimalData, HitEventTO, ObjectHitAnimData, SetResourceData,
-    SetWeaponsData, UpdateHealthData, UpdatePlayerData,
+    AddAnimalData, AllyPingData, BiomeData, ChatBubbleData, ClanRequestData, ClansData, DeathData,
+    HitEventTO, IdleWarningData, KillFeedData, LeaderboardData, MinimapData, ObjectHitAnimData,
+    SetResourceData, SetWeaponsData, SpawnRejectedData, StoreUpdateData, SystemMessageData,
+    TimeSyncData, UpdateAgeData, UpdateHealthData, UpdatePlayerData, UpgradeOptionsData,
+};
+use shared::to_server::{
+    AimMessage, ChatMessage, ChooseUpgradeMessage, ClanAcceptMessage, ClanCreateMessage,
+    ClanJoinMessage, ClanKickMessage, ClanLeaveMessage, ClientMessages, HitMessage, MapPingMessage,
+    MoveMessage, StoreBuyMessage, StoreEquipMessage, UseItemMessage,
 };
-use shared::to_server::{AimMessage, ClientMessages, HitMessage, MoveMessage};
 use shared::{
     PacketType,
     structs::server::{Aim, Move},
@@ -311,6 +317,39 @@ fn decode_bytes_inner(bytes: &[u8]) -> Result<JsValue, JsValue> {
                 decode_packet!(*code, &bytes[1..], ObjectHitAnimData)
             }
             Some(PacketType::SetResource) => decode_packet!(*code, &bytes[1..], SetResourceData),
+            Some(PacketType::UseItem) => decode_packet!(*code, &bytes[1..], UseItemMessage),
+            Some(PacketType::UpdateAge) => decode_packet!(*code, &bytes[1..], UpdateAgeData),
+            Some(PacketType::UpgradeOptions) => {
+                decode_packet!(*code, &bytes[1..], UpgradeOptionsData)
+            }
+            Some(PacketType::ChooseUpgrade) => {
+                decode_packet!(*code, &bytes[1..], ChooseUpgradeMessage)
+            }
+            Some(PacketType::StoreBuy) => decode_packet!(*code, &bytes[1..], StoreBuyMessage),
+            Some(PacketType::StoreEquip) => decode_packet!(*code, &bytes[1..], StoreEquipMessage),
+            Some(PacketType::StoreUpdate) => decode_packet!(*code, &bytes[1..], StoreUpdateData),
+            Some(PacketType::Biomes) => decode_packet!(*code, &bytes[1..], BiomeData),
+            Some(PacketType::TimeSync) => decode_packet!(*code, &bytes[1..], TimeSyncData),
+            Some(PacketType::Chat) => decode_packet!(*code, &bytes[1..], ChatMessage),
+            Some(PacketType::ChatBubble) => decode_packet!(*code, &bytes[1..], ChatBubbleData),
+            Some(PacketType::SystemMessage) => {
+                decode_packet!(*code, &bytes[1..], SystemMessageData)
+            }
+            Some(PacketType::ClanCreate) => decode_packet!(*code, &bytes[1..], ClanCreateMessage),
+            Some(PacketType::ClanJoin) => decode_packet!(*code, &bytes[1..], ClanJoinMessage),
+            Some(PacketType::ClanLeave) => decode_packet!(*code, &bytes[1..], ClanLeaveMessage),
+            Some(PacketType::ClanAccept) => decode_packet!(*code, &bytes[1..], ClanAcceptMessage),
+            Some(PacketType::ClanKick) => decode_packet!(*code, &bytes[1..], ClanKickMessage),
+            Some(PacketType::ClanUpdate) => decode_packet!(*code, &bytes[1..], ClansData),
+            Some(PacketType::ClanRequest) => decode_packet!(*code, &bytes[1..], ClanRequestData),
+            Some(PacketType::Leaderboard) => decode_packet!(*code, &bytes[1..], LeaderboardData),
+            Some(PacketType::KillFeed) => decode_packet!(*code, &bytes[1..], KillFeedData),
+            Some(PacketType::Death) => decode_packet!(*code, &bytes[1..], DeathData),
+            Some(PacketType::MapPing) => decode_packet!(*code, &bytes[1..], MapPingMessage),
+            Some(PacketType::Minimap) => decode_packet!(*code, &bytes[1..], MinimapData),
+            Some(PacketType::AllyPing) => decode_packet!(*code, &bytes[1..], AllyPingData),
+            Some(PacketType::SpawnRejected) => decode_packet!(*code, &bytes[1..], SpawnRejectedData),
+            Some(PacketType::IdleWarning) => decode_packet!(*code, &bytes[1..], IdleWarningData),
             None => Err(JsValue::from_str("unknown opcode")),
         },
         None => Err(JsValue::from_str("no opcode found")),
@@ -355,6 +394,94 @@ pub fn encode_into_bytes(packet: JsValue, opcode: u8) -> Result<Box<[u8]>, JsVal
                 return Err(JsValue::from_str(&format!("error encoding move {}", e)));
             }
         }
+        12 => {
+            let js_use_item: UseItemMessage = serde_wasm_bindgen::from_value(packet)
+                .map_err(|x| JsValue::from_str(&x.to_string()))?;
+
+            if let Err(e) = borsh::to_writer(&mut buf, &js_use_item) {
+                return Err(JsValue::from_str(&format!("error encoding use item {}", e)));
+            }
+        }
+        15 => {
+            let js_upgrade: ChooseUpgradeMessage = serde_wasm_bindgen::from_value(packet)
+                .map_err(|x| JsValue::from_str(&x.to_string()))?;
+
+            if let Err(e) = borsh::to_writer(&mut buf, &js_upgrade) {
+                return Err(JsValue::from_str(&format!("error encoding upgrade {}", e)));
+            }
+        }
+        16 => {
+            let js_buy: StoreBuyMessage = serde_wasm_bindgen::from_value(packet)
+                .map_err(|x| JsValue::from_str(&x.to_string()))?;
+
+            if let Err(e) = borsh::to_writer(&mut buf, &js_buy) {
+                return Err(JsValue::from_str(&format!("error encoding store buy {}", e)));
+            }
+        }
+        17 => {
+            let js_equip: StoreEquipMessage = serde_wasm_bindgen::from_value(packet)
+                .map_err(|x| JsValue::from_str(&x.to_string()))?;
+
+            if let Err(e) = borsh::to_writer(&mut buf, &js_equip) {
+                return Err(JsValue::from_str(&format!("error encoding store equip {}", e)));
+            }
+        }
+        21 => {
+            let js_chat: ChatMessage = serde_wasm_bindgen::from_value(packet)
+                .map_err(|x| JsValue::from_str(&x.to_string()))?;
+
+            if let Err(e) = borsh::to_writer(&mut buf, &js_chat) {
+                return Err(JsValue::from_str(&format!("error encoding chat {}", e)));
+            }
+        }
+        24 => {
+            let js_create: ClanCreateMessage = serde_wasm_bindgen::from_value(packet)
+                .map_err(|x| JsValue::from_str(&x.to_string()))?;
+
+            if let Err(e) = borsh::to_writer(&mut buf, &js_create) {
+                return Err(JsValue::from_str(&format!("error encoding clan create {}", e)));
+            }
+        }
+        25 => {
+            let js_join: ClanJoinMessage = serde_wasm_bindgen::from_value(packet)
+                .map_err(|x| JsValue::from_str(&x.to_string()))?;
+
+            if let Err(e) = borsh::to_writer(&mut buf, &js_join) {
+                return Err(JsValue::from_str(&format!("error encoding clan join {}", e)));
+            }
+        }
+        26 => {
+            let js_leave: ClanLeaveMessage = serde_wasm_bindgen::from_value(packet)
+                .map_err(|x| JsValue::from_str(&x.to_string()))?;
+
+            if let Err(e) = borsh::to_writer(&mut buf, &js_leave) {
+                return Err(JsValue::from_str(&format!("error encoding clan leave {}", e)));
+            }
+        }
+        27 => {
+            let js_accept: ClanAcceptMessage = serde_wasm_bindgen::from_value(packet)
+                .map_err(|x| JsValue::from_str(&x.to_string()))?;
+
+            if let Err(e) = borsh::to_writer(&mut buf, &js_accept) {
+                return Err(JsValue::from_str(&format!("error encoding clan accept {}", e)));
+            }
+        }
+        28 => {
+            let js_kick: ClanKickMessage = serde_wasm_bindgen::from_value(packet)
+                .map_err(|x| JsValue::from_str(&x.to_string()))?;
+
+            if let Err(e) = borsh::to_writer(&mut buf, &js_kick) {
+                return Err(JsValue::from_str(&format!("error encoding clan kick {}", e)));
+            }
+        }
+        34 => {
+            let js_ping: MapPingMessage = serde_wasm_bindgen::from_value(packet)
+                .map_err(|x| JsValue::from_str(&x.to_string()))?;
+
+            if let Err(e) = borsh::to_writer(&mut buf, &js_ping) {
+                return Err(JsValue::from_str(&format!("error encoding map ping {}", e)));
+            }
+        }
         _ => return Err(JsValue::from_str("unknown opcode")),
     }
 
@@ -222,7 +222,7 @@ export class Game {
   };
 
   async enterGame() {
-    const spawnData = { name: document.getElementById("nameInput").value };
+    const spawnData = { name: document.getElementById("nameInput").value, near_clan: true };
     await this.sendEncrypted(spawnData, 1);
     console.log("spawn msg sent");
     document.getElementById("mainMenuContainer").remove();
@@ -351,11 +351,12 @@ export class Game {
         }
 
       case 11: {
-        const { wood, stone, food } = packet.data;
+        const { wood, stone, food, gold } = packet.data;
         if (wood)
           this.resourceDisplay.updateResource("wood", wood);
         // this.resourceDisplay.updateResource("stone", stone);
         // this.resourceDisplay.updateResource("food", food);
+        // this.resourceDisplay.updateResource("gold", gold);
         break;
       }
 